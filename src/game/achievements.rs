@@ -0,0 +1,173 @@
+/// This module contains the achievements a profile can unlock, and the logic for telling
+/// from the events of a game which of them have been earned.
+use std::{fmt, vec};
+use serde::{Deserialize, Serialize};
+use crate::game::components::{ai, arsenal, player, ship};
+use crate::game::{game, replay, rules};
+
+
+/// The most shots in a row a player can miss and still earn the steady aim achievement.
+const STEADY_AIM_STREAK: usize = 5;
+
+/// The number of shots a game has to be won in to earn the quick win achievement.
+const QUICK_WIN_SHOTS: usize = 40;
+
+/// An enum defining every achievement that can be unlocked.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Achievement {
+    SteadyAim,
+    CarrierFirst,
+    QuickWin,
+    HardWin,
+    ArsenalWin,
+    MobileFleetWin,
+    TetrisFleetWin,
+    TeamWin,
+}
+
+impl fmt::Display for Achievement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Achievement::SteadyAim => write!(f, "Steady Aim"),
+            Achievement::CarrierFirst => write!(f, "Big Game Hunter"),
+            Achievement::QuickWin => write!(f, "Blitz"),
+            Achievement::HardWin => write!(f, "Giant Slayer"),
+            Achievement::ArsenalWin => write!(f, "Demolition Expert"),
+            Achievement::MobileFleetWin => write!(f, "Moving Target"),
+            Achievement::TetrisFleetWin => write!(f, "Odd Shapes"),
+            Achievement::TeamWin => write!(f, "Team Player"),
+        }
+    }
+}
+
+impl Achievement {
+    /// A static array containing every achievement to iterate over.
+    pub const ALL: [Achievement; 8] = [
+        Achievement::SteadyAim,
+        Achievement::CarrierFirst,
+        Achievement::QuickWin,
+        Achievement::HardWin,
+        Achievement::ArsenalWin,
+        Achievement::MobileFleetWin,
+        Achievement::TetrisFleetWin,
+        Achievement::TeamWin,
+    ];
+
+    /// Generate a consuming iterator over the achievements
+    pub fn iter() -> impl Iterator<Item = Achievement> {
+        Self::ALL.into_iter()
+    }
+
+    /// Get a short description of what it takes to unlock the achievement.
+    pub fn description(&self) -> String {
+        match self {
+            Achievement::SteadyAim => format!("Win without ever missing more than {} shots in a row", STEADY_AIM_STREAK),
+            Achievement::CarrierFirst => "Make the Carrier the first enemy ship you sink".to_string(),
+            Achievement::QuickWin => format!("Win in under {} shots", QUICK_WIN_SHOTS),
+            Achievement::HardWin => "Beat the Hard computer".to_string(),
+            Achievement::ArsenalWin => "Win an Arsenal game".to_string(),
+            Achievement::MobileFleetWin => "Win a Mobile Fleet game".to_string(),
+            Achievement::TetrisFleetWin => "Win a Tetris Fleet game".to_string(),
+            Achievement::TeamWin => "Win a team game".to_string(),
+        }
+    }
+}
+
+/// A struct holding the achievements a profile has unlocked, along with when.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Achievements {
+    // milliseconds since the unix epoch
+    unlocked: vec::Vec<(Achievement, u64)>,
+}
+
+impl Achievements {
+    pub fn get_unlocked(&self) -> &[(Achievement, u64)] {
+        &self.unlocked
+    }
+
+    /// Returns true if the achievement has been unlocked.
+    pub fn has(&self, achievement: Achievement) -> bool {
+        self.unlocked.iter().any(|(other, _)| *other == achievement)
+    }
+
+    /// Get when the achievement was unlocked, if it has been.
+    pub fn unlocked_at(&self, achievement: Achievement) -> Option<u64> {
+        self.unlocked.iter().find(|(other, _)| *other == achievement).map(|(_, time)| *time)
+    }
+
+    /// Unlock an achievement now. Returns false if it was already unlocked.
+    pub fn unlock(&mut self, achievement: Achievement) -> bool {
+        if self.has(achievement) {
+            return false;
+        }
+        self.unlocked.push((achievement, replay::now()));
+        true
+    }
+}
+
+/// Find every achievement the player in the given seat has earned so far, by going through the
+/// events recorded for the game. Achievements that call for a win are only earned once the
+/// player or their team has won.
+pub fn earned(game: &game::Game, seat: usize, won: bool) -> vec::Vec<Achievement> {
+    let replay: &replay::Replay = game.get_replay();
+    let mut players: vec::Vec<player::Player> = replay.get_names().iter().map(|name| player::Player::new(name)).collect();
+    let mut shots: usize = 0;
+    let mut misses: usize = 0;
+    let mut longest_misses: usize = 0;
+    // the ships sunk by the player's first shot to sink anything
+    let mut first_sunk: Option<vec::Vec<ship::ShipType>> = None;
+
+    for entry in replay.get_entries() {
+        match &entry.event {
+            replay::Event::Place { seat, ship_type, cells, orientation, mirrored } => {
+                players[*seat].add_ship(cells.clone(), *ship_type, *orientation, *mirrored);
+            },
+            replay::Event::Fire { seat: shooter, target, shot } => {
+                let afloat: vec::Vec<bool> = players[*target].get_ships().iter().map(|ship| !players[*target].is_ship_sunk(ship)).collect();
+                let outcome: arsenal::Outcome = players[*target].take_shot(shot);
+                if *shooter != seat {
+                    continue;
+                }
+
+                shots += 1;
+                match outcome {
+                    arsenal::Outcome::Strike { hits: 0, .. } => {
+                        misses += 1;
+                        longest_misses = longest_misses.max(misses);
+                    },
+                    arsenal::Outcome::Strike { .. } => misses = 0,
+                    // sonar pings don't aim to hit anything
+                    arsenal::Outcome::Sonar(_) => {},
+                }
+
+                // decoys aren't ships, so sinking one doesn't count as sinking the first ship
+                let sunk: vec::Vec<ship::ShipType> = players[*target].get_ships().iter()
+                    .zip(afloat)
+                    .filter(|(ship, afloat)| *afloat && players[*target].is_ship_sunk(ship) && !ship.get_type().is_decoy())
+                    .map(|(ship, _)| ship.get_type())
+                    .collect();
+                if first_sunk.is_none() && !sunk.is_empty() {
+                    first_sunk = Some(sunk);
+                }
+            },
+            replay::Event::Move { seat, index, ship_move } => {
+                players[*seat].move_ship(*index, *ship_move);
+            },
+        }
+    }
+
+    let game_rules: &rules::GameRules = game.get_rules();
+    Achievement::iter()
+        .filter(|achievement| match achievement {
+            Achievement::SteadyAim => won && longest_misses <= STEADY_AIM_STREAK,
+            Achievement::CarrierFirst => first_sunk.as_ref()
+                .is_some_and(|sunk| sunk.iter().any(|ship_type| matches!(ship_type, ship::ShipType::Carrier(..)))),
+            Achievement::QuickWin => won && shots < QUICK_WIN_SHOTS,
+            Achievement::HardWin => won && game.computer_difficulty() == Some(ai::Difficulty::Hard),
+            Achievement::ArsenalWin => won && game_rules.mode == rules::GameMode::Arsenal,
+            Achievement::MobileFleetWin => won && game_rules.mode == rules::GameMode::MobileFleet,
+            Achievement::TetrisFleetWin => won && game_rules.mode == rules::GameMode::TetrisFleet,
+            Achievement::TeamWin => won && game_rules.teams,
+        })
+        .collect()
+}
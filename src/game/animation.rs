@@ -0,0 +1,123 @@
+/// This module contains the animations played when a shot lands. An animation is a list of frames,
+/// each marking some cells of the board that was fired at with a spark drawn over them, and is
+/// played one frame per tick while the screen is redrawn.
+use std::{time, vec};
+use crate::game::components::{arsenal, board, player};
+
+
+/// An enum defining every effect that can be drawn over a cell during an animation.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Spark {
+    /// The cells about to be fired at, blinking.
+    Target,
+    /// A miss landing in the water, and the ripple it leaves behind.
+    Splash,
+    Ripple,
+    /// A hit exploding, and the flash that follows it.
+    Blast,
+    Flash,
+    /// A cell of a ship that was just sunk, flashing with the rest of its outline.
+    Outline,
+}
+
+/// A single frame of an animation, holding the cells that are drawn differently from the board.
+type Frame = vec::Vec<((usize, usize), Spark)>;
+
+/// How many frames the target blinks for before the shot lands, and how long the outline of a sunk ship flashes for.
+const AIM_FRAMES: usize = 6;
+const SUNK_FRAMES: usize = 6;
+
+/// The sparks a hit and a miss go through as they land, one per frame.
+const HIT_SPARKS: [Spark; 4] = [Spark::Blast, Spark::Flash, Spark::Blast, Spark::Flash];
+const MISS_SPARKS: [Spark; 4] = [Spark::Splash, Spark::Ripple, Spark::Splash, Spark::Ripple];
+
+/// A struct holding what a board looked like before a shot, to tell what the shot changed.
+pub struct Before {
+    area: vec::Vec<((usize, usize), board::CellState)>,
+    afloat: vec::Vec<usize>,
+    sonar: bool,
+}
+
+impl Before {
+    /// Take note of the cells a shot is about to cover and of the ships still afloat.
+    pub fn take(player: &player::Player, shot: &arsenal::Shot) -> Self {
+        Self {
+            area: shot.area().into_iter().map(|(r, c)| ((r, c), player.get_public_state(r, c))).collect(),
+            afloat: (0..player.get_ships().len()).filter(|i| !player.is_ship_sunk(&player.get_ships()[*i])).collect(),
+            sonar: shot.weapon == arsenal::Weapon::Sonar,
+        }
+    }
+}
+
+/// A struct holding the frames of an animation and the one being shown.
+#[derive(Clone)]
+pub struct Animation {
+    // the seat of the player whose board the animation is drawn on
+    target: usize,
+    frames: vec::Vec<Frame>,
+    frame: usize,
+    tick: time::Duration,
+}
+
+impl Animation {
+    /// Create the animation of a shot at the given seat, from what its board looked like before and
+    /// after the shot. The target blinks, then every cell the shot changed splashes or explodes,
+    /// and finally the outline of every ship the shot sank flashes.
+    pub fn new(target: usize, before: Before, player: &player::Player, tick: time::Duration) -> Self {
+        let area: vec::Vec<(usize, usize)> = before.area.iter().map(|(cell, _)| *cell).collect();
+        let mut frames: vec::Vec<Frame> = (0..AIM_FRAMES)
+            .map(|i| match i % 2 {
+                0 => area.iter().map(|cell| (*cell, Spark::Target)).collect(),
+                _ => vec![],
+            })
+            .collect();
+
+        // sonar doesn't touch the board, so there is nothing to land. cells that were already fired at
+        // are left as they are, as are the ones a torpedo didn't reach
+        if !before.sonar {
+            let landed: vec::Vec<((usize, usize), bool)> = before.area.iter()
+                .filter(|((r, c), state)| player.get_public_state(*r, *c) != *state)
+                .map(|((r, c), _)| ((*r, *c), player.get_public_state(*r, *c) == board::CellState::HitShip))
+                .collect();
+            for i in 0..HIT_SPARKS.len() {
+                frames.push(landed.iter()
+                    .map(|(cell, hit)| (*cell, if *hit { HIT_SPARKS[i] } else { MISS_SPARKS[i] }))
+                    .collect());
+            }
+
+            let sunk: vec::Vec<(usize, usize)> = before.afloat.iter()
+                .map(|i| &player.get_ships()[*i])
+                .filter(|ship| player.is_ship_sunk(ship))
+                .flat_map(|ship| ship.get_cells().iter().copied())
+                .collect();
+            if !sunk.is_empty() {
+                frames.extend((0..SUNK_FRAMES).map(|i| match i % 2 {
+                    0 => sunk.iter().map(|cell| (*cell, Spark::Outline)).collect(),
+                    _ => vec![],
+                }));
+            }
+        }
+        Self { target, frames, frame: 0, tick }
+    }
+
+    /// Get the seat of the player whose board the animation is drawn on.
+    pub fn get_target(&self) -> usize {
+        self.target
+    }
+
+    /// Get how long each frame is shown for.
+    pub fn get_tick(&self) -> time::Duration {
+        self.tick
+    }
+
+    /// Get the cells drawn differently in the frame being shown.
+    pub fn get_frame(&self) -> &[((usize, usize), Spark)] {
+        self.frames.get(self.frame).map_or(&[], |frame| frame)
+    }
+
+    /// Move on to the next frame. Returns false once the animation is over.
+    pub fn advance(&mut self) -> bool {
+        self.frame += 1;
+        self.frame < self.frames.len()
+    }
+}
@@ -0,0 +1,386 @@
+/// This module contains the targeting strategies used by computer players.
+use std::{fmt, vec};
+use rand::{self, Rng, SeedableRng, rngs::StdRng, seq::SliceRandom};
+use serde::{Deserialize, Serialize};
+use super::{arsenal, board, player, ship};
+
+
+/// An enum that defines how hard the computer plays.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Difficulty::Easy => write!(f, "Easy"),
+            Difficulty::Normal => write!(f, "Normal"),
+            Difficulty::Hard => write!(f, "Hard")
+        }
+    }
+}
+
+impl Difficulty {
+    // a static array of every difficulty, from easiest to hardest
+    pub const ALL: [Difficulty; 3] = [
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard
+    ];
+
+    /// Generate a consuming iterator over the Difficulty options
+    pub fn iter() -> impl Iterator<Item = Difficulty> {
+        Self::ALL.into_iter()
+    }
+}
+
+/// What the computer is allowed to know about a single cell of its opponent's board.
+#[derive(Clone, Copy, PartialEq)]
+enum Knowledge {
+    Unknown,
+    Miss,
+    Hit,
+    Sunk,
+}
+
+/// The four directions the computer looks in when following up on a hit.
+const DIRECTIONS: [ship::ShipOrientation; 4] = [
+    ship::ShipOrientation::Left,
+    ship::ShipOrientation::Up,
+    ship::ShipOrientation::Right,
+    ship::ShipOrientation::Down
+];
+
+/// Every move a ship can make in the mobile fleet game mode.
+const SHIP_MOVES: [ship::ShipMove; 5] = [
+    ship::ShipMove::Step(ship::ShipOrientation::Left),
+    ship::ShipMove::Step(ship::ShipOrientation::Up),
+    ship::ShipMove::Step(ship::ShipOrientation::Right),
+    ship::ShipMove::Step(ship::ShipOrientation::Down),
+    ship::ShipMove::Rotate
+];
+
+/// A struct that encapsulates a computer player's strategy and memory.
+#[derive(Serialize, Deserialize)]
+pub struct Ai {
+    difficulty: Difficulty,
+    // seats and centers of sonar pings that made contact and haven't been fully explored yet
+    leads: vec::Vec<(usize, (usize, usize))>,
+    // every decision draws from a generator seeded with the seed and the number of decisions
+    // made so far, so a saved game carries on exactly as it would have
+    seed: u64,
+    decisions: u64,
+}
+
+impl Ai {
+    /// Create a new computer strategy for the given difficulty.
+    pub fn new(difficulty: Difficulty) -> Self {
+        Self { difficulty, leads: vec![], seed: rand::random(), decisions: 0 }
+    }
+
+    pub fn get_difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    /// Get the random number generator for the next decision.
+    fn next_rng(&mut self) -> StdRng {
+        self.decisions += 1;
+        StdRng::seed_from_u64(self.seed.wrapping_add(self.decisions))
+    }
+
+    /// Pick which of the opponents, given with their seats, to fire at next. The easy computer picks
+    /// at random, while the others go after wounded ships first and then the weakest fleet.
+    pub fn choose_target(&mut self, opponents: &[(usize, &player::Player)]) -> usize {
+        let mut rng: StdRng = self.next_rng();
+        if self.difficulty == Difficulty::Easy {
+            return opponents.choose(&mut rng).map(|(seat, _)| *seat).unwrap_or(0);
+        }
+
+        // count the cells of each fleet that are still afloat, and whether any of them are wounded
+        opponents.iter()
+            .map(|(seat, opponent)| {
+                let knowledge: vec::Vec<Knowledge> = Self::read_board(opponent);
+                let wounded: bool = knowledge.contains(&Knowledge::Hit)
+                    || self.leads.iter().any(|(lead_seat, _)| lead_seat == seat);
+                let afloat: usize = opponent.get_ships().iter()
+                    .filter(|ship| !opponent.is_ship_sunk(ship))
+                    .map(|ship| ship.get_type().size())
+                    .sum();
+                (*seat, wounded, afloat)
+            })
+            .max_by_key(|(_, wounded, afloat)| (*wounded, usize::MAX - afloat))
+            .map(|(seat, _, _)| seat)
+            .unwrap_or(0)
+    }
+
+    /// Pick the next shot to fire at the opponent in the given seat, using the special weapons left in the arsenal.
+    pub fn choose_shot(&mut self, seat: usize, opponent: &player::Player, arsenal: &arsenal::Arsenal) -> arsenal::Shot {
+        let knowledge: vec::Vec<Knowledge> = Self::read_board(opponent);
+        let mut rng: StdRng = self.next_rng();
+
+        // the easy computer fires at random, and occasionally wastes a special weapon on a random cell
+        if self.difficulty == Difficulty::Easy {
+            let target: (usize, usize) = Self::random_unknown(&knowledge, false, &mut rng);
+            let specials: vec::Vec<arsenal::Weapon> = arsenal::Weapon::iter()
+                .filter(|w| *w != arsenal::Weapon::Cannon && arsenal.can_fire(*w))
+                .collect();
+            if !specials.is_empty() && rng.gen_bool(0.25) {
+                // only torpedoes travel in a direction, so the heading of other weapons is left as is
+                let weapon: arsenal::Weapon = specials[rng.gen_range(0..specials.len())];
+                let heading: ship::ShipOrientation = match weapon {
+                    arsenal::Weapon::Torpedo => rng.sample(rand::distributions::Standard),
+                    _ => ship::ShipOrientation::Right,
+                };
+                return arsenal::Shot { weapon, target, heading };
+            }
+            return arsenal::Shot::cannon(target.0, target.1);
+        }
+
+        // finish off any ship that has been hit before looking for new ones
+        if let Some(target) = self.follow_up(&knowledge, opponent, &mut rng) {
+            return arsenal::Shot::cannon(target.0, target.1);
+        }
+
+        // follow up on sonar contacts, bombing them if possible
+        self.leads.retain(|(lead_seat, lead)| {
+            *lead_seat != seat || arsenal::Weapon::Sonar.area(*lead, ship::ShipOrientation::Right).iter()
+                .any(|(r, c)| knowledge[r * board::COLS + c] == Knowledge::Unknown)
+        });
+        if let Some(lead) = self.leads.iter().find(|(lead_seat, _)| *lead_seat == seat).map(|(_, lead)| *lead) {
+            if arsenal.can_fire(arsenal::Weapon::Bomb) {
+                return arsenal::Shot { weapon: arsenal::Weapon::Bomb, target: lead, heading: ship::ShipOrientation::Right };
+            }
+            let density: vec::Vec<usize> = Self::density(&knowledge, opponent);
+            let target: (usize, usize) = Self::best_cell(
+                &density,
+                arsenal::Weapon::Sonar.area(lead, ship::ShipOrientation::Right).into_iter()
+                    .filter(|(r, c)| knowledge[r * board::COLS + c] == Knowledge::Unknown)
+            ).unwrap_or(lead);
+            return arsenal::Shot::cannon(target.0, target.1);
+        }
+
+        // otherwise use the special weapons where they are most likely to find something
+        let density: vec::Vec<usize> = Self::density(&knowledge, opponent);
+        for weapon in [arsenal::Weapon::Sonar, arsenal::Weapon::Bomb, arsenal::Weapon::Torpedo] {
+            if arsenal.can_fire(weapon) {
+                return Self::best_special(weapon, &knowledge, &density, &mut rng);
+            }
+        }
+
+        // hunt for a new ship. the hard computer always picks the most likely cell, while
+        // the normal computer picks randomly on a checkerboard pattern
+        let target: (usize, usize) = match self.difficulty {
+            Difficulty::Hard => Self::best_cell(&density, Self::unknown_cells(&knowledge))
+                .unwrap_or_else(|| Self::random_unknown(&knowledge, false, &mut rng)),
+            _ => Self::random_unknown(&knowledge, true, &mut rng),
+        };
+        arsenal::Shot::cannon(target.0, target.1)
+    }
+
+    /// Let the strategy learn from the outcome of a shot it fired at the opponent in the given seat.
+    pub fn observe(&mut self, seat: usize, shot: &arsenal::Shot, outcome: &arsenal::Outcome) {
+        if let arsenal::Outcome::Sonar(true) = outcome {
+            self.leads.push((seat, shot.target));
+        }
+    }
+
+    /// Decide whether to move one of the computer's own ships instead of firing. The computer moves
+    /// a ship away when the opponent's last shot landed on or right next to it.
+    pub fn choose_move(&mut self, own: &player::Player, incoming: Option<&arsenal::Shot>) -> Option<(usize, ship::ShipMove)> {
+        let mut rng: StdRng = self.next_rng();
+
+        // the easy computer occasionally shuffles a random ship around
+        if self.difficulty == Difficulty::Easy {
+            if !rng.gen_bool(0.1) {
+                return None;
+            }
+            let index: usize = *own.movable_ships().choose(&mut rng)?;
+            let moves: vec::Vec<ship::ShipMove> = SHIP_MOVES.into_iter()
+                .filter(|m| own.try_move_ship(index, *m).is_some())
+                .collect();
+            return moves.choose(&mut rng).map(|m| (index, *m));
+        }
+
+        // the normal computer only reacts half of the time
+        if self.difficulty == Difficulty::Normal && rng.gen_bool(0.5) {
+            return None;
+        }
+
+        let area: vec::Vec<(usize, usize)> = incoming?.area();
+        let distance = |cells: &[(usize, usize)]| {
+            cells.iter()
+                .flat_map(|(r, c)| area.iter().map(move |(sr, sc)| r.abs_diff(*sr) + c.abs_diff(*sc)))
+                .min()
+                .unwrap_or(usize::MAX)
+        };
+
+        for index in own.movable_ships() {
+            let current: usize = distance(own.get_ships()[index].get_cells());
+            if current > 1 {
+                continue;
+            }
+
+            // pick the move that takes the ship furthest away from the shot
+            let best: Option<(ship::ShipMove, usize)> = SHIP_MOVES.into_iter()
+                .filter_map(|m| own.try_move_ship(index, m).map(|cells| (m, distance(&cells))))
+                .max_by_key(|(_, d)| *d);
+            if let Some((ship_move, d)) = best && d > current {
+                return Some((index, ship_move));
+            }
+        }
+        None
+    }
+
+    /// Build the computer's view of the opponent's board.
+    fn read_board(opponent: &player::Player) -> vec::Vec<Knowledge> {
+        let mut knowledge: vec::Vec<Knowledge> = vec![Knowledge::Unknown; board::ROWS * board::COLS];
+        for r in 0..board::ROWS {
+            for c in 0..board::COLS {
+                knowledge[r * board::COLS + c] = match opponent.get_public_state(r, c) {
+                    board::CellState::Guessed => Knowledge::Miss,
+                    board::CellState::HitShip if opponent.is_sunk_at(r, c) => Knowledge::Sunk,
+                    board::CellState::HitShip => Knowledge::Hit,
+                    _ => Knowledge::Unknown,
+                };
+            }
+        }
+        knowledge
+    }
+
+    /// Get the neighbour of a cell in the given direction, if it is on the board.
+    fn step(cell: (usize, usize), direction: ship::ShipOrientation) -> Option<(usize, usize)> {
+        let (dr, dc) = direction.delta();
+        let (r, c) = (cell.0 as isize + dr, cell.1 as isize + dc);
+        if board::Board::in_bounds(r, c) { Some((r as usize, c as usize)) } else { None }
+    }
+
+    /// Pick a cell next to a hit on a ship that hasn't been sunk yet. Cells that extend
+    /// a line of hits are preferred over any other neighbour.
+    fn follow_up(&self, knowledge: &[Knowledge], opponent: &player::Player, rng: &mut StdRng) -> Option<(usize, usize)> {
+        let at = |cell: Option<(usize, usize)>, k: Knowledge| {
+            cell.is_some_and(|(r, c)| knowledge[r * board::COLS + c] == k)
+        };
+
+        let hits: vec::Vec<(usize, usize)> = (0..board::ROWS * board::COLS)
+            .filter(|i| knowledge[*i] == Knowledge::Hit)
+            .map(|i| (i / board::COLS, i % board::COLS))
+            .collect();
+
+        let mut in_line: vec::Vec<(usize, usize)> = vec![];
+        let mut adjacent: vec::Vec<(usize, usize)> = vec![];
+        for hit in hits.iter() {
+            for direction in DIRECTIONS {
+                let next: Option<(usize, usize)> = Self::step(*hit, direction);
+                if !at(next, Knowledge::Unknown) {
+                    continue;
+                }
+                if at(Self::step(*hit, direction.next().next()), Knowledge::Hit) {
+                    in_line.push(next.unwrap());
+                } else {
+                    adjacent.push(next.unwrap());
+                }
+            }
+        }
+
+        let candidates: vec::Vec<(usize, usize)> = if in_line.is_empty() { adjacent } else { in_line };
+        if self.difficulty == Difficulty::Hard {
+            let density: vec::Vec<usize> = Self::density(knowledge, opponent);
+            Self::best_cell(&density, candidates.into_iter())
+        } else {
+            candidates.choose(rng).copied()
+        }
+    }
+
+    /// Count, for every unknown cell, how many ways the opponent's remaining ships could cover it,
+    /// taking every way each ship can be turned and flipped into account.
+    fn density(knowledge: &[Knowledge], opponent: &player::Player) -> vec::Vec<usize> {
+        let mut density: vec::Vec<usize> = vec![0; board::ROWS * board::COLS];
+        let open = |r: usize, c: usize| matches!(knowledge[r * board::COLS + c], Knowledge::Unknown | Knowledge::Hit);
+
+        for ship in opponent.get_ships().iter().filter(|ship| !opponent.is_ship_sunk(ship)) {
+            let ship_type: ship::ShipType = ship.get_type();
+            for (orient, mirrored) in ship_type.variants() {
+                for r in 0..board::ROWS {
+                    for c in 0..board::COLS {
+                        // count every placement of the ship's shape that only covers open cells
+                        let cells: vec::Vec<(isize, isize)> = ship_type.footprint((r, c), orient, mirrored);
+                        if cells.iter().all(|(r, c)| board::Board::in_bounds(*r, *c) && open(*r as usize, *c as usize)) {
+                            cells.iter().for_each(|(r, c)| density[*r as usize * board::COLS + *c as usize] += 1);
+                        }
+                    }
+                }
+            }
+        }
+
+        // only unknown cells are worth shooting at
+        for (i, k) in knowledge.iter().enumerate() {
+            if *k != Knowledge::Unknown {
+                density[i] = 0;
+            }
+        }
+        density
+    }
+
+    /// Get every cell that hasn't been shot at yet.
+    fn unknown_cells(knowledge: &[Knowledge]) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..board::ROWS * board::COLS)
+            .filter(|i| knowledge[*i] == Knowledge::Unknown)
+            .map(|i| (i / board::COLS, i % board::COLS))
+    }
+
+    /// Pick the candidate cell with the highest density, if any.
+    fn best_cell(density: &[usize], candidates: impl Iterator<Item = (usize, usize)>) -> Option<(usize, usize)> {
+        candidates.max_by_key(|(r, c)| density[r * board::COLS + c])
+    }
+
+    /// Pick a random cell that hasn't been shot at yet. With parity, only every
+    /// other cell is considered, since every ship covers at least two cells.
+    fn random_unknown(knowledge: &[Knowledge], parity: bool, rng: &mut StdRng) -> (usize, usize) {
+        let mut cells: vec::Vec<(usize, usize)> = Self::unknown_cells(knowledge)
+            .filter(|(r, c)| !parity || (r + c) % 2 == 0)
+            .collect();
+        if cells.is_empty() {
+            cells = Self::unknown_cells(knowledge).collect();
+        }
+        cells.choose(rng).copied().unwrap_or((0, 0))
+    }
+
+    /// Aim a special weapon where it covers the most likely cells.
+    fn best_special(weapon: arsenal::Weapon, knowledge: &[Knowledge], density: &[usize], rng: &mut StdRng) -> arsenal::Shot {
+        let mut best: (usize, arsenal::Shot) = (0, arsenal::Shot::cannon(0, 0));
+        let headings: &[ship::ShipOrientation] = match weapon {
+            arsenal::Weapon::Torpedo => &DIRECTIONS,
+            _ => &[ship::ShipOrientation::Right],
+        };
+
+        for r in 0..board::ROWS {
+            for c in 0..board::COLS {
+                for heading in headings {
+                    // torpedoes are always launched from the edge of the board
+                    let (dr, dc) = heading.delta();
+                    if weapon == arsenal::Weapon::Torpedo && board::Board::in_bounds(r as isize - dr, c as isize - dc) {
+                        continue;
+                    }
+
+                    let shot = arsenal::Shot { weapon, target: (r, c), heading: *heading };
+                    let score: usize = shot.area().iter()
+                        .filter(|(r, c)| knowledge[r * board::COLS + c] == Knowledge::Unknown)
+                        .map(|(r, c)| density[r * board::COLS + c])
+                        .sum();
+                    if score > best.0 {
+                        best = (score, shot);
+                    }
+                }
+            }
+        }
+
+        if best.0 == 0 {
+            let (r, c) = Self::random_unknown(knowledge, false, rng);
+            return arsenal::Shot { weapon, target: (r, c), heading: ship::ShipOrientation::Right };
+        }
+        best.1
+    }
+}
@@ -0,0 +1,169 @@
+/// This module contains logic for the limited-use weapons of the arsenal game mode.
+use std::{fmt, vec};
+use serde::{Deserialize, Serialize};
+use super::{board, ship};
+
+
+/// An enum that defines every weapon a player can fire on their turn.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Weapon {
+    Cannon,
+    Bomb,
+    Torpedo,
+    Sonar,
+}
+
+impl fmt::Display for Weapon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Weapon::Cannon => write!(f, "Cannon"),
+            Weapon::Bomb => write!(f, "Bomb"),
+            Weapon::Torpedo => write!(f, "Torpedo"),
+            Weapon::Sonar => write!(f, "Sonar")
+        }
+    }
+}
+
+impl Weapon {
+    // a static array of all the weapons, in the order they are cycled through
+    // in the main loop
+    pub const ALL: [Weapon; 4] = [
+        Weapon::Cannon,
+        Weapon::Bomb,
+        Weapon::Torpedo,
+        Weapon::Sonar
+    ];
+
+    /// Generate a consuming iterator over the Weapon options
+    pub fn iter() -> impl Iterator<Item = Weapon> {
+        Self::ALL.into_iter()
+    }
+
+    /// Get the cells covered by this weapon when aimed at the target cell. The heading
+    /// is only used by the torpedo, which travels from the target towards the edge of the board.
+    pub fn area(&self, target: (usize, usize), heading: ship::ShipOrientation) -> vec::Vec<(usize, usize)> {
+        let mut cells: vec::Vec<(usize, usize)> = vec![];
+        match self {
+            Weapon::Cannon => cells.push(target),
+            Weapon::Bomb | Weapon::Sonar => {
+                // a 3x3 square centered on the target, clipped to the board
+                for dr in -1..=1 {
+                    for dc in -1..=1 {
+                        let (r, c) = (target.0 as isize + dr, target.1 as isize + dc);
+                        if board::Board::in_bounds(r, c) {
+                            cells.push((r as usize, c as usize));
+                        }
+                    }
+                }
+            },
+            Weapon::Torpedo => {
+                let (dr, dc) = heading.delta();
+                let (mut r, mut c) = (target.0 as isize, target.1 as isize);
+                while board::Board::in_bounds(r, c) {
+                    cells.push((r as usize, c as usize));
+                    r += dr;
+                    c += dc;
+                }
+            }
+        }
+        cells
+    }
+}
+
+/// A struct that tracks how many uses of each special weapon a player has left.
+/// The cannon is always available and is not tracked.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Arsenal {
+    bombs: usize,
+    torpedoes: usize,
+    sonars: usize,
+}
+
+impl Arsenal {
+    /// An arsenal without any special weapons, used by game modes that don't have them.
+    pub fn empty() -> Self {
+        Self { bombs: 0, torpedoes: 0, sonars: 0 }
+    }
+
+    /// The loadout each player starts with in the arsenal game mode.
+    pub fn standard() -> Self {
+        Self { bombs: 1, torpedoes: 2, sonars: 2 }
+    }
+
+    /// Get the number of uses left for a weapon, or None if the weapon is unlimited.
+    pub fn remaining(&self, weapon: Weapon) -> Option<usize> {
+        match weapon {
+            Weapon::Cannon => None,
+            Weapon::Bomb => Some(self.bombs),
+            Weapon::Torpedo => Some(self.torpedoes),
+            Weapon::Sonar => Some(self.sonars),
+        }
+    }
+
+    /// Returns true if the weapon has at least one use left.
+    pub fn can_fire(&self, weapon: Weapon) -> bool {
+        self.remaining(weapon) != Some(0)
+    }
+
+    /// Returns true if any special weapon has uses left.
+    pub fn has_specials(&self) -> bool {
+        self.bombs + self.torpedoes + self.sonars > 0
+    }
+
+    /// Use up one charge of the given weapon.
+    pub fn consume(&mut self, weapon: Weapon) {
+        match weapon {
+            Weapon::Cannon => {},
+            Weapon::Bomb => self.bombs = self.bombs.saturating_sub(1),
+            Weapon::Torpedo => self.torpedoes = self.torpedoes.saturating_sub(1),
+            Weapon::Sonar => self.sonars = self.sonars.saturating_sub(1),
+        }
+    }
+}
+
+/// A single shot fired at an opponent's board.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Shot {
+    pub weapon: Weapon,
+    pub target: (usize, usize),
+    pub heading: ship::ShipOrientation,
+}
+
+impl Shot {
+    /// Create a regular cannon shot at the given cell.
+    pub fn cannon(row: usize, col: usize) -> Self {
+        Self { weapon: Weapon::Cannon, target: (row, col), heading: ship::ShipOrientation::Right }
+    }
+
+    /// Get the cells this shot covers.
+    pub fn area(&self) -> vec::Vec<(usize, usize)> {
+        self.weapon.area(self.target, self.heading)
+    }
+}
+
+impl fmt::Display for Shot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.weapon, board::Board::label(self.target.0, self.target.1))
+    }
+}
+
+/// The result of a shot, reported back to the player who fired it.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Outcome {
+    /// The number of ship cells that were hit and the number of cells that were missed.
+    Strike { hits: usize, misses: usize },
+    /// Whether a sonar ping found any undamaged ship cells in its area.
+    Sonar(bool),
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Strike { hits: 0, misses: _ } => write!(f, "miss"),
+            Outcome::Strike { hits: 1, misses: 0 } => write!(f, "HIT"),
+            Outcome::Strike { hits, misses } => write!(f, "{} hit(s), {} miss(es)", hits, misses),
+            Outcome::Sonar(true) => write!(f, "contact"),
+            Outcome::Sonar(false) => write!(f, "no contact"),
+        }
+    }
+}
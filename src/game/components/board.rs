@@ -1,6 +1,6 @@
 /// This module contains logic for managing board state.
-use std::{fmt, vec};
-use colored::Colorize;
+use std::vec;
+use serde::{Deserialize, Serialize};
 use super::ship;
 
 
@@ -11,7 +11,7 @@ pub const COLS: usize = 10;
 /// An enum that defines all possible states a board cell can exist in.
 /// When a cell is modified on the board, we simply adjust the enumeration
 /// assigned to that cell.
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum CellState {
     Empty,
     Guessed,
@@ -21,39 +21,15 @@ pub enum CellState {
     InvalidPlacement,
 }
 
-impl fmt::Display for CellState {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let cell_content = match self {
-            CellState::Empty => "□".black(),
-            CellState::Guessed => "▣".white(),
-            CellState::OwnShip(ship_type) => {
-                match ship_type {
-                    ship::ShipType::Carrier(_, symbol)
-                    | ship::ShipType::Battleship(_, symbol)
-                    | ship::ShipType::Destroyer(_, symbol)
-                    | ship::ShipType::Submarine(_, symbol)
-                    | ship::ShipType::PatrolBoat(_, symbol) => {
-                        format!("{}", symbol).green()
-                    }
-                }
-            }
-            CellState::HitShip => "◼".red(),
-            CellState::Highlighted => "◼".blue(),
-            CellState::InvalidPlacement => "X".red(),
-        };
-        write!(f, "{}", cell_content)
-    }
-}
-
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Cell {
     state: CellState,
     prev_state: CellState
 }
 
-impl fmt::Display for Cell {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.state)
+impl Default for Cell {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -101,19 +77,55 @@ impl Cell {
 }
 
 /// A structure for encapsulating board state and logic.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Board {
     cells: vec::Vec<Cell>
 }
 
-impl Board {
+impl Default for Board {
     /// Generate a new board of empty cells.
-    pub fn default() -> Self {
+    fn default() -> Self {
         let mut cells: vec::Vec<Cell> = vec![];
         for _ in 0..(ROWS*COLS) {
             cells.push(Cell::new());
         }
         Self { cells }
     }
+}
+
+impl Board {
+    /// Returns true if the given row and column lie on the board.
+    pub fn in_bounds(row: isize, col: isize) -> bool {
+        row >= 0 && col >= 0 && (row as usize) < ROWS && (col as usize) < COLS
+    }
+
+    /// Get the letter a row is labelled with, e.g. 1 => 'B'.
+    pub fn row_label(row: usize) -> char {
+        (b'A' + row as u8) as char
+    }
+
+    /// Get the number a column is labelled with, e.g. 6 => "7".
+    pub fn col_label(col: usize) -> String {
+        (col + 1).to_string()
+    }
+
+    /// Format a row and column index as a human readable coordinate, e.g. (1, 6) => "B7".
+    pub fn label(row: usize, col: usize) -> String {
+        format!("{}{}", Board::row_label(row), Board::col_label(col))
+    }
+
+    /// Parse a human readable coordinate into a row and column index, e.g. "B7" => (1, 6).
+    /// Lowercase letters are accepted. Returns None if the coordinate is not on the board.
+    pub fn parse_label(label: &str) -> Option<(usize, usize)> {
+        let mut chars = label.trim().chars();
+        let letter: char = chars.next()?.to_ascii_uppercase();
+        if !letter.is_ascii_uppercase() {
+            return None;
+        }
+        let row: usize = (letter as u8 - b'A') as usize;
+        let col: usize = chars.as_str().parse::<usize>().ok()?.checked_sub(1)?;
+        if row < ROWS && col < COLS { Some((row, col)) } else { None }
+    }
 
     /// Set a cell in the board to the specified new state.
     pub fn set(&mut self, row: usize, col: usize, state: CellState) {
@@ -147,52 +159,26 @@ impl Board {
         }
     }
 
-    /// Checks whether a ship placement is valid.
+    /// Checks whether a ship placement is valid. Temporary highlights on the board are ignored.
     pub fn try_place_ship(
         &self, 
         r: usize, 
         c: usize, 
         orient: ship::ShipOrientation, 
+        mirrored: bool,
         ship_type: ship::ShipType
     ) -> Option<vec::Vec<(usize, usize)>> {
-        // starting from the given cell, check if the ship can fit in the given orientation
+        // starting from the given cell, check if the ship's shape fits in the given orientation
         // without overlapping any existing ships or going out of bounds
-        let ship_length: usize = ship_type.size();
-        let mut indices: Option<vec::Vec<(usize, usize)>> = Some(vec![]);
-
-        for i in 0..ship_length {
-            match orient {
-                ship::ShipOrientation::Up => {
-                    if (i > r) || self.get(r - i, c).get_state() != CellState::Empty {
-                        indices = None;
-                        break;
-                    }
-                    indices.as_mut().unwrap().push((r - i, c));
-                },
-                ship::ShipOrientation::Down => {
-                    if (i + r >= ROWS) || self.get(r + i, c).get_state() != CellState::Empty {
-                        indices = None;
-                        break;
-                    }
-                    indices.as_mut().unwrap().push((r + i, c));
-                },
-                ship::ShipOrientation::Left => {
-                    if (i > c) || self.get(r, c - i).get_state() != CellState::Empty {
-                        indices = None;
-                        break;
-                    }
-                    indices.as_mut().unwrap().push((r, c - i));
-                },
-                ship::ShipOrientation::Right => {
-                    if (i + c >= COLS) || self.get(r, c + i).get_state() != CellState::Empty {
-                        indices = None;
-                        break;
-                    }
-                    indices.as_mut().unwrap().push((r, c + i));
-                }
+        let mut indices: vec::Vec<(usize, usize)> = vec![];
+
+        for (row, col) in ship_type.footprint((r, c), orient, mirrored) {
+            if !Board::in_bounds(row, col) || self.get(row as usize, col as usize).get_prev_state() != CellState::Empty {
+                return None;
             }
+            indices.push((row as usize, col as usize));
         };
 
-        indices
+        Some(indices)
     }   
 }
@@ -1,3 +1,5 @@
+pub mod ai;
+pub mod arsenal;
 pub mod board;
 pub mod player;
 pub mod ship;
\ No newline at end of file
@@ -1,26 +1,52 @@
 /// This module contains logic for managing player state.
 use std::vec;
 use rand::{self, Rng};
-use super::{board, ship};
+use serde::{Deserialize, Serialize};
+use super::{arsenal, board, ship};
 
 
+/// An error returned when a player's ships could not be placed automatically.
+#[derive(Debug, thiserror::Error)]
+#[error("could not place all ships after {0} attempts")]
+pub struct PlacementError(pub usize);
+
 /// A struct for encapsulating player logic and state.
+#[derive(Serialize, Deserialize)]
 pub struct Player {
-    name: &'static str,
+    name: String,
+    // the profile the player plays as, which their results are credited to
+    profile: Option<String>,
     board: board::Board,
     ships: vec::Vec<ship::Ship>,
+    arsenal: arsenal::Arsenal,
 }
 
 impl Player {
     /// Create a new player with the given name and default ships and board layouts.
-    pub fn new(name: &'static str) -> Self {
+    pub fn new(name: &str) -> Self {
         Self {
-            name: name,
+            name: name.to_string(),
+            profile: None,
             board: board::Board::default(),
             ships: vec![],  // at player creation, they don't have any placed ships yet
+            arsenal: arsenal::Arsenal::empty(),
         }
     }
 
+    /// Create a new player playing as the given profile, and named after it.
+    pub fn for_profile(profile: &str) -> Self {
+        Self { profile: Some(profile.to_string()), ..Self::new(profile) }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the profile the player plays as, if they play as one.
+    pub fn get_profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
     pub fn get_cell(&self, row: usize, col: usize) -> &board::Cell {
         self.board.get(row, col)
     }
@@ -33,102 +59,269 @@ impl Player {
         }
     }
 
+    /// Get the state of a cell as the opponent is allowed to know it. Temporary
+    /// highlights are ignored and ships that have not been hit are hidden.
+    pub fn get_public_state(&self, row: usize, col: usize) -> board::CellState {
+        match self.board.get(row, col).get_prev_state() {
+            board::CellState::OwnShip(_) => board::CellState::Empty,
+            state => state
+        }
+    }
+
     pub fn get_cell_mut(&mut self, row: usize, col: usize) -> &mut board::Cell {
         self.board.get_mut(row, col)
     }
 
-    /// Set a ship on the player's board.
-    pub fn add_ship(&mut self, cell_indices: vec::Vec<(usize, usize)>, ship_type: ship::ShipType) {
-        let ship: ship::Ship = ship::Ship::new(cell_indices.clone());
+    /// Undo every temporary highlight on the player's board.
+    pub fn clear_highlights(&mut self) {
+        for r in 0..board::ROWS {
+            for c in 0..board::COLS {
+                self.board.undo(r, c);
+            }
+        }
+    }
+
+    pub fn get_ships(&self) -> &[ship::Ship] {
+        &self.ships
+    }
+
+    pub fn get_arsenal(&self) -> &arsenal::Arsenal {
+        &self.arsenal
+    }
+
+    pub fn get_arsenal_mut(&mut self) -> &mut arsenal::Arsenal {
+        &mut self.arsenal
+    }
+
+    /// Hand the player a new set of special weapons.
+    pub fn set_arsenal(&mut self, arsenal: arsenal::Arsenal) {
+        self.arsenal = arsenal;
+    }
+
+    /// Check whether a ship could be placed on the player's board, returning the cells it would cover.
+    pub fn try_place_ship(
+        &self,
+        row: usize,
+        col: usize,
+        orient: ship::ShipOrientation,
+        mirrored: bool,
+        ship_type: ship::ShipType
+    ) -> Option<vec::Vec<(usize, usize)>> {
+        self.board.try_place_ship(row, col, orient, mirrored, ship_type)
+    }
+
+    /// Set a ship on the player's board. The cells are the ones returned by `try_place_ship`
+    /// for the given orientation and mirroring.
+    pub fn add_ship(
+        &mut self,
+        cell_indices: vec::Vec<(usize, usize)>,
+        ship_type: ship::ShipType,
+        orient: ship::ShipOrientation,
+        mirrored: bool
+    ) {
+        let ship: ship::Ship = ship::Ship::new(ship_type, cell_indices, orient, mirrored);
         self.ships.push(ship);
         // update the board cells to reflect the ship placement
-        for (row, col) in cell_indices {
-            self.board.set(row, col, board::CellState::OwnShip(ship_type))
+        self.sync_board();
+    }
+
+    /// Re-derive the ship cells of the board from the positions of the player's ships.
+    /// Hits and misses stay where they are, since they are never moved.
+    fn sync_board(&mut self) {
+        for r in 0..board::ROWS {
+            for c in 0..board::COLS {
+                if let board::CellState::OwnShip(_) = self.board.get(r, c).get_prev_state() {
+                    self.board.set(r, c, board::CellState::Empty);
+                }
+            }
+        }
+        for ship in self.ships.iter() {
+            for (row, col) in ship.get_cells() {
+                if self.board.get(*row, *col).get_prev_state() == board::CellState::Empty {
+                    self.board.set(*row, *col, board::CellState::OwnShip(ship.get_type()));
+                }
+            }
         }
     }
 
-    /// Returns true if all of the player's ships are sunk.
+    /// Check whether one of the player's ships can make the given move, returning the cells it
+    /// would cover afterwards. Only undamaged ships can move, and they have to follow the same
+    /// rules as when they were first placed.
+    pub fn try_move_ship(&self, index: usize, ship_move: ship::ShipMove) -> Option<vec::Vec<(usize, usize)>> {
+        let ship: &ship::Ship = self.ships.get(index)?;
+        if ship.is_damaged(&self.board) {
+            return None;
+        }
+
+        // lift the ship off of a copy of the board so it doesn't block its own move
+        let mut board: board::Board = self.board.clone();
+        for (row, col) in ship.get_cells() {
+            board.set(*row, *col, board::CellState::Empty);
+        }
+
+        let ((row, col), orient) = ship.destination(ship_move)?;
+        board.try_place_ship(row, col, orient, ship.is_mirrored(), ship.get_type())
+    }
+
+    /// Move one of the player's ships. Returns true if the move was valid and has been made.
+    pub fn move_ship(&mut self, index: usize, ship_move: ship::ShipMove) -> bool {
+        if let Some(cells) = self.try_move_ship(index, ship_move)
+            && let Some((_, orient)) = self.ships[index].destination(ship_move) {
+            self.ships[index].relocate(cells, orient);
+            self.sync_board();
+            return true;
+        }
+        false
+    }
+
+    /// Get the indices of the ships that are still allowed to move.
+    pub fn movable_ships(&self) -> vec::Vec<usize> {
+        (0..self.ships.len()).filter(|i| !self.ships[*i].is_damaged(&self.board)).collect()
+    }
+
+    /// Returns true if all of the player's ships are sunk. Decoys don't count.
     pub fn all_ships_sunk(&self) -> bool {
         for ship in self.ships.iter() {
-            if !ship.is_sunk(&self.board) {
+            if !ship.get_type().is_decoy() && !ship.is_sunk(&self.board) {
                 return false;
             }
         }
         true
     }
 
-    /// Apply a guess to the player's board and return the resulting cell state.
-    pub fn guess(&mut self, row: usize, col: usize) {
+    /// Returns true if the given ship of this player has been sunk.
+    pub fn is_ship_sunk(&self, ship: &ship::Ship) -> bool {
+        ship.is_sunk(&self.board)
+    }
+
+    /// Count how many cells of the given ship of this player have been hit.
+    pub fn ship_hits(&self, ship: &ship::Ship) -> usize {
+        ship.hits(&self.board)
+    }
+
+    /// Returns true if the cell at the given position belongs to a ship that has been sunk.
+    pub fn is_sunk_at(&self, row: usize, col: usize) -> bool {
+        self.ships.iter().any(|ship| ship.get_cells().contains(&(row, col)) && self.is_ship_sunk(ship))
+    }
+
+    /// Apply a guess to the player's board and return the resulting cell state, or
+    /// None if the cell had already been guessed.
+    pub fn guess(&mut self, row: usize, col: usize) -> Option<board::CellState> {
         let cell: &mut board::Cell = self.get_cell_mut(row, col);
         match cell.get_prev_state() {
             board::CellState::OwnShip(_) => {
                 self.board.update(row, col, board::CellState::HitShip);
+                Some(board::CellState::HitShip)
             },
             board::CellState::Empty => {
                 self.board.update(row, col, board::CellState::Guessed);
+                Some(board::CellState::Guessed)
             },
-            _ => {} // do nothing for other cell states
+            _ => None // do nothing for other cell states
         }
     }
 
-    pub fn auto_guess(&mut self) {
-        let mut rng = rand::thread_rng();
-        loop {
-            let r = rng.gen_range(0..board::ROWS);
-            let c = rng.gen_range(0..board::COLS);
-            let cell: &board::Cell = self.get_cell(r, c);
-            match cell.get_state() {
-                board::CellState::HitShip | board::CellState::Guessed => {
-                    continue; // already guessed, try again
-                },
-                _ => {
-                    // valid guess
-                    self.guess(r, c);
-                    break;
-                }
+    /// Returns true if the shot covers at least one cell of the player's board that hasn't been fired at
+    /// yet. Shots that don't can't tell the opponent anything new, so they aren't taken.
+    pub fn can_take_shot(&self, shot: &arsenal::Shot) -> bool {
+        shot.area().iter().any(|(r, c)| !matches!(self.get_public_state(*r, *c), board::CellState::Guessed | board::CellState::HitShip))
+    }
+
+    /// Apply a shot from the opponent to the player's board and return its outcome.
+    pub fn take_shot(&mut self, shot: &arsenal::Shot) -> arsenal::Outcome {
+        let area: vec::Vec<(usize, usize)> = shot.area();
+
+        // sonar doesn't touch the board, it only reports if any part of a ship is still afloat in the area
+        if shot.weapon == arsenal::Weapon::Sonar {
+            return arsenal::Outcome::Sonar(area.iter().any(|(r, c)| {
+                matches!(self.get_cell(*r, *c).get_prev_state(), board::CellState::OwnShip(_))
+            }));
+        }
+
+        let (mut hits, mut misses) = (0, 0);
+        for (r, c) in area {
+            // torpedoes stop at the first ship in their path, even if it was already hit
+            let ship_in_path: bool = matches!(
+                self.get_cell(r, c).get_prev_state(),
+                board::CellState::OwnShip(_) | board::CellState::HitShip
+            );
+
+            match self.guess(r, c) {
+                Some(board::CellState::HitShip) => hits += 1,
+                Some(_) => misses += 1,
+                None => {}
+            }
+
+            if shot.weapon == arsenal::Weapon::Torpedo && ship_in_path {
+                break;
             }
         }
+        arsenal::Outcome::Strike { hits, misses }
+    }
+
+    /// Remove every ship from the player's board.
+    pub fn clear_ships(&mut self) {
+        self.ships.clear();
+        self.board = board::Board::default();
     }
 
     /// Automatically place all ships for the player. This is used for
     /// computer players / players who want to randomly setup their boards.
     pub fn auto_place_ships(
         &mut self,
-        max_tries_per_ship: usize, 
+        fleet: &[ship::ShipType],
+        max_tries_per_ship: usize,
+        max_global_restarts: usize
+    ) -> Result<(), PlacementError> {
+        self.clear_ships();
+        self.auto_place_remaining(fleet, max_tries_per_ship, max_global_restarts)
+    }
+
+    /// Automatically place the given ships around the ones the player has already placed.
+    /// If they can't all be placed, the player's ships are left as they were.
+    pub fn auto_place_remaining(
+        &mut self,
+        ships: &[ship::ShipType],
+        max_tries_per_ship: usize,
         max_global_restarts: usize
-    ) -> Result<(), ()>{
+    ) -> Result<(), PlacementError> {
         // we use a simple retry algorithm that keeps trying to place ships
         // until all ships are placed successfully
         let mut rng = rand::thread_rng();
+        let placed_before: usize = self.ships.len();
 
-        for _ in 0..max_global_restarts {
-            // reset the board to be a default empty board
-            self.ships.clear();
-            self.board = board::Board::default();
+        'restart: for _ in 0..max_global_restarts {
+            // take away the ships placed by the previous attempt
+            self.ships.truncate(placed_before);
+            self.sync_board();
 
-            for ship_type in ship::ShipType::ALL.iter() {
+            for ship_type in ships.iter() {
 
                 let mut placed: bool = false;
                 for _ in 0..max_tries_per_ship {
                     let orient: ship::ShipOrientation = rand::random();
+                    // only mirror ships whose shape actually changes when flipped
+                    let mirrored: bool = ship_type.variants().iter().any(|(_, mirrored)| *mirrored) && rng.gen_bool(0.5);
                     let r = rng.gen_range(0..board::ROWS);
                     let c = rng.gen_range(0..board::COLS);
 
-                    let indices: Option<vec::Vec<(usize, usize)>> = self.board.try_place_ship(r, c, orient, *ship_type);
+                    let indices: Option<vec::Vec<(usize, usize)>> = self.board.try_place_ship(r, c, orient, mirrored, *ship_type);
                     if let Some(cell_indices) = indices {
-                        self.add_ship(cell_indices, *ship_type);
+                        self.add_ship(cell_indices, *ship_type, orient, mirrored);
                         placed = true;
                         break;
-                    } 
+                    }
                 }
 
                 if !placed {
-                    continue; // restart the global placement process
+                    continue 'restart; // restart the global placement process
                 }
             }
-            return Ok(()); 
+            return Ok(());
         }
 
-        Err(())
+        self.ships.truncate(placed_before);
+        self.sync_board();
+        Err(PlacementError(max_global_restarts))
     }
 }
@@ -1,10 +1,12 @@
 /// This module contains logic for managing and creating ships.
 use std::{fmt, vec};
 use rand::distributions::{Distribution, Standard};
+use serde::{Deserialize, Serialize};
 use super::board;
 
 
 /// An enum to represent the orientation of a ship.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum ShipOrientation {
     Left,
     Up,
@@ -22,6 +24,37 @@ impl ShipOrientation {
             ShipOrientation::Down => ShipOrientation::Left,
         }
     }
+
+    /// Get the (row, col) step taken when moving one cell in this orientation.
+    pub fn delta(&self) -> (isize, isize) {
+        match self {
+            ShipOrientation::Left => (0, -1),
+            ShipOrientation::Up => (-1, 0),
+            ShipOrientation::Right => (0, 1),
+            ShipOrientation::Down => (1, 0),
+        }
+    }
+
+    /// Rotate a (row, col) offset of a ship's shape, which is defined facing right,
+    /// so that it faces in this orientation.
+    pub fn rotate(&self, offset: (isize, isize)) -> (isize, isize) {
+        let (r, c) = offset;
+        match self {
+            ShipOrientation::Right => (r, c),
+            ShipOrientation::Down => (c, -r),
+            ShipOrientation::Left => (-r, -c),
+            ShipOrientation::Up => (-c, r),
+        }
+    }
+}
+
+/// An enum to represent a move a ship can make in the mobile fleet game mode.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ShipMove {
+    // move the whole ship one cell in the given direction
+    Step(ShipOrientation),
+    // turn the ship clockwise around its first cell
+    Rotate,
 }
 
 /// Implement the rand::Distribution trait for ShipOrientation in order to randomly
@@ -38,13 +71,18 @@ impl Distribution<ShipOrientation> for Standard {
 }
 
 /// An enum that defines all possible ship types for the game.
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum ShipType {
     Carrier(usize, char),
     Battleship(usize, char),
     Destroyer(usize, char),
     Submarine(usize, char),
     PatrolBoat(usize, char),
+    Decoy(usize, char),
+    LShip(usize, char),
+    TShip(usize, char),
+    PlusShip(usize, char),
+    BlockShip(usize, char),
 }
 
 impl fmt::Display for ShipType {
@@ -54,7 +92,12 @@ impl fmt::Display for ShipType {
             ShipType::Battleship(_, _) => write!(f, "Battleship"),
             ShipType::Destroyer(_, _) => write!(f, "Destroyer"),
             ShipType::Submarine(_, _) => write!(f, "Submarine"),
-            ShipType::PatrolBoat (_, _)=> write!(f, "Patrol Boat")
+            ShipType::PatrolBoat (_, _)=> write!(f, "Patrol Boat"),
+            ShipType::Decoy(_, _) => write!(f, "Decoy"),
+            ShipType::LShip(_, _) => write!(f, "L-Ship"),
+            ShipType::TShip(_, _) => write!(f, "T-Ship"),
+            ShipType::PlusShip(_, _) => write!(f, "Plus Ship"),
+            ShipType::BlockShip(_, _) => write!(f, "Block Ship")
         }
     }
 }
@@ -72,6 +115,19 @@ impl ShipType {
         ShipType::PatrolBoat(2, 'P')
     ];
 
+    // the decoy is not part of the standard fleet. it is only handed out
+    // in game modes that allow for it (see rules::GameRules::fleet)
+    pub const DECOY: ShipType = ShipType::Decoy(2, 'd');
+
+    // the fleet for the "tetris fleet" game mode, made up of polyomino shaped ships
+    pub const TETRIS: [ShipType; 5] = [
+        ShipType::PlusShip(5, '+'),
+        ShipType::LShip(4, 'L'),
+        ShipType::TShip(4, 'T'),
+        ShipType::BlockShip(4, 'O'),
+        ShipType::Battleship(4, 'B')
+    ];
+
     /// Generate a consuming iterator over the ShipType options
     pub fn iter() -> impl Iterator<Item = ShipType> {
         Self::ALL.into_iter()
@@ -84,7 +140,12 @@ impl ShipType {
             | ShipType::Battleship(size, _)
             | ShipType::Destroyer(size, _)
             | ShipType::Submarine(size, _)
-            | ShipType::PatrolBoat(size, _) => *size,
+            | ShipType::PatrolBoat(size, _)
+            | ShipType::Decoy(size, _)
+            | ShipType::LShip(size, _)
+            | ShipType::TShip(size, _)
+            | ShipType::PlusShip(size, _)
+            | ShipType::BlockShip(size, _) => *size,
         }
     }
 
@@ -95,31 +156,139 @@ impl ShipType {
             | ShipType::Battleship(_, symbol)
             | ShipType::Destroyer(_, symbol)
             | ShipType::Submarine(_, symbol)
-            | ShipType::PatrolBoat(_, symbol) => *symbol,
+            | ShipType::PatrolBoat(_, symbol)
+            | ShipType::Decoy(_, symbol)
+            | ShipType::LShip(_, symbol)
+            | ShipType::TShip(_, symbol)
+            | ShipType::PlusShip(_, symbol)
+            | ShipType::BlockShip(_, symbol) => *symbol,
+        }
+    }
+
+    /// Get the (row, col) offsets of every cell of this ship, relative to its first cell,
+    /// when the ship is facing right. Straight ships are a line of cells of their size.
+    pub fn shape(&self) -> vec::Vec<(isize, isize)> {
+        match self {
+            ShipType::LShip(_, _) => vec![(0, 0), (0, 1), (0, 2), (1, 0)],
+            ShipType::TShip(_, _) => vec![(0, 0), (0, 1), (0, 2), (1, 1)],
+            ShipType::PlusShip(_, _) => vec![(0, 0), (0, 1), (0, 2), (-1, 1), (1, 1)],
+            ShipType::BlockShip(_, _) => vec![(0, 0), (0, 1), (1, 0), (1, 1)],
+            _ => (0..self.size() as isize).map(|i| (0, i)).collect(),
+        }
+    }
+
+    /// Get the board positions this ship would cover when its first cell is at the anchor,
+    /// facing the given orientation and optionally mirrored along its length. Positions
+    /// may lie outside of the board.
+    pub fn footprint(&self, anchor: (usize, usize), orient: ShipOrientation, mirrored: bool) -> vec::Vec<(isize, isize)> {
+        self.shape().into_iter()
+            .map(|(r, c)| orient.rotate(if mirrored { (-r, c) } else { (r, c) }))
+            .map(|(r, c)| (anchor.0 as isize + r, anchor.1 as isize + c))
+            .collect()
+    }
+
+    /// Get every distinct way this ship can be turned and mirrored. Symmetric shapes
+    /// have fewer variants, e.g. the block only has one.
+    pub fn variants(&self) -> vec::Vec<(ShipOrientation, bool)> {
+        let mut seen: vec::Vec<vec::Vec<(isize, isize)>> = vec![];
+        let mut variants: vec::Vec<(ShipOrientation, bool)> = vec![];
+        for mirrored in [false, true] {
+            for orient in [ShipOrientation::Right, ShipOrientation::Down, ShipOrientation::Left, ShipOrientation::Up] {
+                // normalize the shape so it can be compared regardless of where it is anchored
+                let mut cells: vec::Vec<(isize, isize)> = self.footprint((0, 0), orient, mirrored);
+                let min_r: isize = cells.iter().map(|c| c.0).min().unwrap_or(0);
+                let min_c: isize = cells.iter().map(|c| c.1).min().unwrap_or(0);
+                cells = cells.into_iter().map(|(r, c)| (r - min_r, c - min_c)).collect();
+                cells.sort();
+                if !seen.contains(&cells) {
+                    seen.push(cells);
+                    variants.push((orient, mirrored));
+                }
+            }
         }
+        variants
+    }
+
+    /// Returns true if this ship is a decoy. Decoys look like real ships to the
+    /// opponent, but they do not need to be sunk to win the game.
+    pub fn is_decoy(&self) -> bool {
+        matches!(self, ShipType::Decoy(_, _))
     }
 }
 
 /// A struct to contain all associated data with a ship. 
+#[derive(Serialize, Deserialize)]
 pub struct Ship {
-    cells: vec::Vec<(usize, usize)>
+    ship_type: ShipType,
+    cells: vec::Vec<(usize, usize)>,
+    orientation: ShipOrientation,
+    mirrored: bool,
 }
 
 impl Ship {
-    /// Return a new ship structure with an empty vector of owned cells.
-    pub fn new(cells: vec::Vec<(usize, usize)>) -> Self {
-        Self { cells: cells }
+    /// Return a new ship structure of the given type occupying the given cells. The cells
+    /// have to be in the order given by the ship's footprint, starting from its anchor.
+    pub fn new(ship_type: ShipType, cells: vec::Vec<(usize, usize)>, orientation: ShipOrientation, mirrored: bool) -> Self {
+        Self { ship_type, cells, orientation, mirrored }
     }
 
-    /// Check whether this ship is sunk based on the current board state.
-    pub fn is_sunk(&self, board: &board::Board) -> bool {
-        // check each corresponding cell in the board to see if it's been hit
-        for (row, col) in self.cells.iter() {
-            let cell: &board::Cell = board.get(*row, *col);
-            if cell.get_state() != board::CellState::HitShip {
-                return false;
-            }
+    /// Get the type of this ship.
+    pub fn get_type(&self) -> ShipType {
+        self.ship_type
+    }
+
+    /// Get the cells this ship occupies on the board.
+    pub fn get_cells(&self) -> &[(usize, usize)] {
+        &self.cells
+    }
+
+    /// Get the orientation the ship is facing in.
+    pub fn orientation(&self) -> ShipOrientation {
+        self.orientation
+    }
+
+    /// Returns true if the ship's shape is mirrored.
+    pub fn is_mirrored(&self) -> bool {
+        self.mirrored
+    }
+
+    /// Get the cell and orientation the ship would be placed from after making the given move.
+    /// Returns None if the move would take the first cell of the ship off the board.
+    pub fn destination(&self, ship_move: ShipMove) -> Option<((usize, usize), ShipOrientation)> {
+        let first: (usize, usize) = *self.cells.first()?;
+        match ship_move {
+            ShipMove::Step(direction) => {
+                let (dr, dc) = direction.delta();
+                let (r, c) = (first.0 as isize + dr, first.1 as isize + dc);
+                if board::Board::in_bounds(r, c) {
+                    Some(((r as usize, c as usize), self.orientation()))
+                } else {
+                    None
+                }
+            },
+            ShipMove::Rotate => Some((first, self.orientation().next())),
         }
-        true
+    }
+
+    /// Move the ship to a new set of cells, facing the given orientation.
+    pub fn relocate(&mut self, cells: vec::Vec<(usize, usize)>, orientation: ShipOrientation) {
+        self.cells = cells;
+        self.orientation = orientation;
+    }
+
+    /// Check whether any part of this ship has been hit.
+    pub fn is_damaged(&self, board: &board::Board) -> bool {
+        self.cells.iter().any(|(row, col)| board.get(*row, *col).get_prev_state() == board::CellState::HitShip)
+    }
+
+    /// Count how many cells of this ship have been hit.
+    pub fn hits(&self, board: &board::Board) -> usize {
+        self.cells.iter().filter(|(row, col)| board.get(*row, *col).get_prev_state() == board::CellState::HitShip).count()
+    }
+
+    /// Check whether this ship is sunk based on the current board state. Temporary highlights
+    /// over its cells are ignored.
+    pub fn is_sunk(&self, board: &board::Board) -> bool {
+        self.hits(board) == self.cells.len()
     }
 }
@@ -0,0 +1,97 @@
+/// This module contains fleet formations, which are ship placements a profile has saved so they
+/// can be set up again in later games.
+use std::vec;
+use serde::{Deserialize, Serialize};
+use crate::game::components::{board, player, ship};
+
+
+/// The longest name a formation can be given.
+pub const MAX_FORMATION_NAME: usize = 16;
+
+/// An error returned when a saved formation can't be used for the current game.
+#[derive(Debug, thiserror::Error)]
+pub enum FormationError {
+    #[error("it was saved for a {0}x{1} board")]
+    BoardSize(usize, usize),
+    #[error("it was saved for a different fleet")]
+    Fleet,
+    #[error("the {0} doesn't fit on the board")]
+    Placement(String),
+}
+
+/// A struct describing where a single ship of a formation is placed.
+#[derive(Clone, Serialize, Deserialize)]
+struct Placement {
+    ship_type: ship::ShipType,
+    anchor: (usize, usize),
+    orientation: ship::ShipOrientation,
+    mirrored: bool,
+}
+
+/// A struct holding a named placement of a whole fleet.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Formation {
+    name: String,
+    // the size of the board the formation was saved on
+    rows: usize,
+    cols: usize,
+    placements: vec::Vec<Placement>,
+}
+
+impl Formation {
+    /// Create a formation from the ships the player has placed.
+    pub fn from_player(name: &str, player: &player::Player) -> Self {
+        let placements: vec::Vec<Placement> = player.get_ships().iter()
+            .filter_map(|ship| Some(Placement {
+                ship_type: ship.get_type(),
+                anchor: *ship.get_cells().first()?,
+                orientation: ship.orientation(),
+                mirrored: ship.is_mirrored(),
+            }))
+            .collect();
+        Self { name: name.to_string(), rows: board::ROWS, cols: board::COLS, placements }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns true if the formation places exactly the ships of the given fleet.
+    pub fn matches_fleet(&self, fleet: &[ship::ShipType]) -> bool {
+        let mut remaining: vec::Vec<ship::ShipType> = fleet.to_vec();
+        for placement in self.placements.iter() {
+            match remaining.iter().position(|ship_type| *ship_type == placement.ship_type) {
+                Some(i) => { remaining.remove(i); },
+                None => return false,
+            }
+        }
+        remaining.is_empty()
+    }
+
+    /// Replace the player's ships with the ones in the formation. The formation is checked against
+    /// the board size and the fleet first, and the player is left untouched if it doesn't fit.
+    pub fn apply(&self, player: &mut player::Player, fleet: &[ship::ShipType]) -> Result<(), FormationError> {
+        if (self.rows, self.cols) != (board::ROWS, board::COLS) {
+            return Err(FormationError::BoardSize(self.rows, self.cols));
+        }
+        if !self.matches_fleet(fleet) {
+            return Err(FormationError::Fleet);
+        }
+
+        // place every ship on an empty board first, so a bad formation never gets half applied
+        let mut scratch: player::Player = player::Player::new(player.get_name());
+        for placement in self.placements.iter() {
+            let (row, col) = placement.anchor;
+            let cells: vec::Vec<(usize, usize)> = scratch
+                .try_place_ship(row, col, placement.orientation, placement.mirrored, placement.ship_type)
+                .ok_or_else(|| FormationError::Placement(placement.ship_type.to_string()))?;
+            scratch.add_ship(cells, placement.ship_type, placement.orientation, placement.mirrored);
+        }
+
+        player.clear_ships();
+        for ship in scratch.get_ships() {
+            player.add_ship(ship.get_cells().to_vec(), ship.get_type(), ship.orientation(), ship.is_mirrored());
+        }
+        Ok(())
+    }
+}
@@ -1,40 +1,208 @@
 /// Module for housing game logic and management.
-use crate::game::components::{player};
-use crate::game::layouts;
+use crate::game::components::{ai, arsenal, player, ship};
+use crate::game::{achievements, animation, input, layouts, log, render, replay, rules, settings, stats, storage};
+use serde::{Deserialize, Serialize};
 
 use crossterm::{
     cursor,
+    event,
     terminal,
     execute
 };
 
 
 pub enum GameEndReason {
-    PlayerAWon,
-    PlayerBWon,
+    // the player in the given seat is the last one with ships afloat
+    PlayerWon(usize),
+    // the given team is the last one with ships afloat
+    TeamWon(usize),
+    // the game was saved to be continued later
+    Saved,
 }
 
+/// An enum defining who is in control of a seat at the table.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Controller {
+    Human,
+    Computer(ai::Difficulty),
+}
+
+/// An enum defining everything a player can do with their turn.
+pub enum Action {
+    // fire a shot at the board of the player in the given seat
+    Fire(usize, arsenal::Shot),
+    // move one of the player's own ships, given by its index, instead of firing
+    MoveShip(usize, ship::ShipMove),
+    // save the game and leave it, to be continued later
+    SaveAndQuit,
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct Game {
-    player_a: player::Player,
-    player_b: player::Player,
+    players: Vec<player::Player>,
+    // the strategy of each seat, or None if the seat is played by a human
+    computers: Vec<Option<ai::Ai>>,
+    rules: rules::GameRules,
     // we make turn count a 'usize' instead of something like 'u16' or 'u32'
     // because the turn_count is dictated by the board size, and since
     // board size is type 'usize', it would follow that turn_count should be also
     turn_count: usize,
+    // the seat whose turn it is within the current round
+    seat: usize,
+    // the last shot fired at each seat
+    incoming: Vec<Option<arsenal::Shot>>,
+    // everything that has happened so far, shown in the battle log
+    log: Vec<log::Entry>,
+    // the shots each seat has fired so far
+    tallies: Vec<stats::Tally>,
+    // a recording of everything that has happened, to be played back later
+    replay: replay::Replay,
+    // the achievements the human earned for the first time since the game was started or continued
+    #[serde(skip)]
+    earned: Vec<achievements::Achievement>,
 }
 
 impl Game {
-    /// Create a new game instance with the two players and a turn count of 0.
-    pub fn new(player_a: player::Player, player_b: player::Player) -> Self {
-        Self { player_a, player_b, turn_count: 0 }
+    /// Create a new game instance with the players in their seats and a turn count of 0.
+    /// Every player is handed the special weapons allowed by the rules.
+    pub fn new(seats: Vec<(player::Player, Controller)>, rules: rules::GameRules) -> Self {
+        let mut players: Vec<player::Player> = vec![];
+        let mut computers: Vec<Option<ai::Ai>> = vec![];
+        for (mut player, controller) in seats {
+            player.set_arsenal(rules.arsenal());
+            players.push(player);
+            computers.push(match controller {
+                Controller::Human => None,
+                Controller::Computer(difficulty) => Some(ai::Ai::new(difficulty)),
+            });
+        }
+        let incoming: Vec<Option<arsenal::Shot>> = vec![None; players.len()];
+        let tallies: Vec<stats::Tally> = vec![stats::Tally::default(); players.len()];
+        let replay: replay::Replay = replay::Replay::new(rules, &players);
+        Self { players, computers, rules, turn_count: 0, seat: 0, incoming, log: vec![], tallies, replay, earned: vec![] }
+    }
+
+    pub fn get_players(&self) -> &[player::Player] {
+        &self.players
+    }
+
+    pub fn get_rules(&self) -> &rules::GameRules {
+        &self.rules
+    }
+
+    pub fn get_turn_count(&self) -> usize {
+        self.turn_count
+    }
+
+    pub fn get_tallies(&self) -> &[stats::Tally] {
+        &self.tallies
+    }
+
+    pub fn get_replay(&self) -> &replay::Replay {
+        &self.replay
+    }
+
+    pub fn get_earned(&self) -> &[achievements::Achievement] {
+        &self.earned
+    }
+
+    /// Get the seats that are played by humans.
+    pub fn human_seats(&self) -> Vec<usize> {
+        (0..self.players.len()).filter(|seat| self.computers[*seat].is_none()).collect()
+    }
+
+    /// Get the difficulty of the computer players, or None if every seat is played by a human.
+    pub fn computer_difficulty(&self) -> Option<ai::Difficulty> {
+        self.computers.iter().flatten().map(|computer| computer.get_difficulty()).next()
+    }
+
+    /// Get the seats of every player that still has ships afloat.
+    pub fn alive_seats(&self) -> Vec<usize> {
+        (0..self.players.len()).filter(|seat| !self.players[*seat].all_ships_sunk()).collect()
+    }
+
+    /// Get every team that still has a player with ships afloat.
+    pub fn alive_teams(&self) -> Vec<usize> {
+        let mut teams: Vec<usize> = self.alive_seats().into_iter().map(|seat| self.rules.team_of(seat)).collect();
+        teams.sort();
+        teams.dedup();
+        teams
+    }
+
+    /// Get the seats of the other players on the same team as the given seat.
+    pub fn allies_of(&self, seat: usize) -> Vec<usize> {
+        (0..self.players.len())
+            .filter(|other| *other != seat && self.rules.team_of(*other) == self.rules.team_of(seat))
+            .collect()
+    }
+
+    /// Let the computer in the given seat decide what to do with its turn.
+    fn computer_action(&mut self, seat: usize, incoming: Option<&arsenal::Shot>) -> Action {
+        let alive: Vec<usize> = self.alive_seats();
+        let computer: &mut ai::Ai = self.computers[seat].as_mut().expect("seat is not played by a computer");
+
+        // move a ship instead of firing if the rules allow it and the computer wants to
+        if self.rules.ships_can_move()
+            && let Some((index, ship_move)) = computer.choose_move(&self.players[seat], incoming) {
+            return Action::MoveShip(index, ship_move);
+        }
+
+        let opponents: Vec<(usize, &player::Player)> = alive.into_iter()
+            .filter(|other| self.rules.team_of(*other) != self.rules.team_of(seat))
+            .map(|other| (other, &self.players[other]))
+            .collect();
+        let target: usize = computer.choose_target(&opponents);
+        Action::Fire(target, computer.choose_shot(target, &self.players[target], self.players[seat].get_arsenal()))
     }
 
-    pub fn get_player_a(&self) -> &player::Player {
-        &self.player_a
+    /// Record something that happened on the current turn in the battle log.
+    fn record(&mut self, event: log::Event) {
+        self.log.push(log::Entry { turn: self.turn_count, event });
     }
 
-    pub fn get_player_b(&self) -> &player::Player {
-        &self.player_b
+    /// Apply the action of the player in the given seat and record what happened in the battle log.
+    fn apply(&mut self, seat: usize, action: Action) {
+        match action {
+            Action::Fire(target, shot) => {
+                self.replay.push(replay::Event::Fire { seat, target, shot });
+                let afloat: Vec<bool> = self.players[target].get_ships().iter().map(|ship| !self.players[target].is_ship_sunk(ship)).collect();
+                let outcome: arsenal::Outcome = self.players[target].take_shot(&shot);
+                self.players[seat].get_arsenal_mut().consume(shot.weapon);
+                self.tallies[seat].shots += 1;
+                if let arsenal::Outcome::Strike { hits, misses } = outcome {
+                    self.tallies[seat].cells += hits + misses;
+                    self.tallies[seat].hits += hits;
+                }
+                // teammates share what they learn from every shot
+                for ally in 0..self.players.len() {
+                    if self.rules.team_of(ally) == self.rules.team_of(seat)
+                        && let Some(computer) = self.computers[ally].as_mut() {
+                        computer.observe(target, &shot, &outcome);
+                    }
+                }
+
+                self.record(log::Event::Fired { seat, target, shot, outcome });
+                // decoys sinking isn't news, since they don't count towards the fleet
+                let sunk: Vec<ship::ShipType> = self.players[target].get_ships().iter().zip(afloat)
+                    .filter(|(ship, afloat)| *afloat && self.players[target].is_ship_sunk(ship) && !ship.get_type().is_decoy())
+                    .map(|(ship, _)| ship.get_type())
+                    .collect();
+                for ship in sunk {
+                    self.record(log::Event::Sank { seat, target, ship });
+                }
+                if self.players[target].all_ships_sunk() {
+                    self.record(log::Event::Out { seat: target });
+                }
+            },
+            Action::MoveShip(index, ship_move) => {
+                self.replay.push(replay::Event::Move { seat, index, ship_move });
+                self.players[seat].move_ship(index, ship_move);
+                let ship: ship::ShipType = self.players[seat].get_ships()[index].get_type();
+                self.record(log::Event::Moved { seat, ship });
+            },
+            // saving is handled by the game loop, and doesn't change the boards
+            Action::SaveAndQuit => {},
+        }
     }
 
     /// Start the main game loop. At this point in the code,
@@ -45,51 +213,180 @@ impl Game {
 
         // for the entire game loop, we'll be in an alternate terminal, so we do that once here
         terminal::enable_raw_mode()?;
-        let mut out = std::io::stdout();
-        execute!(out, terminal::EnterAlternateScreen, cursor::Hide, terminal::Clear(terminal::ClearType::All))?;
+        let mut out = render::Renderer::stdout();
+        execute!(out, terminal::EnterAlternateScreen, event::EnableMouseCapture, cursor::Hide, terminal::Clear(terminal::ClearType::All))?;
+
+        // every seat keeps its own aim, and remembers the last shot that was fired at it
+        let settings: settings::Settings = storage::load_settings().unwrap_or_default();
+        let keys: input::KeyMap = input::KeyMap::new(&settings.keys);
+        let tick: Option<std::time::Duration> = settings.display.animation_speed.tick();
+        let mut targetings: Vec<layouts::game::main_loop::Targeting> = (0..self.players.len())
+            .map(|seat| layouts::game::main_loop::Targeting {
+                mobile_fleet: self.rules.ships_can_move(),
+                target: (seat + 1) % self.players.len(),
+                allies: self.allies_of(seat),
+                confirm_clicks: settings.mouse.confirm_clicks,
+                keys: keys.clone(),
+                theme: settings.display.theme,
+                ..Default::default()
+            })
+            .collect();
+        let hot_seat: bool = self.computers.iter().filter(|computer| computer.is_none()).count() > 1;
+
+        // a lone human playing as a profile earns achievements, and is told about new ones on their next turn
+        let unlocked: Option<(usize, achievements::Achievements)> = match self.human_seats()[..] {
+            [seat] => self.players[seat].get_profile()
+                .filter(|profile| storage::has_profile(profile))
+                .map(|profile| (seat, storage::load_achievements(profile).unwrap_or_default())),
+            _ => None,
+        };
+        let mut toasts: Vec<achievements::Achievement> = vec![];
 
-        let mut player_a_cursor_pos: (usize, usize) = (0, 0);
-        self.turn_count = 1;
+        // a new game starts on the first turn, while a saved game picks up where it was left
+        if self.turn_count == 0 {
+            self.turn_count = 1;
+        }
 
         let main_loop_exit_option: std::io::Result<GameEndReason> = 'gameLoop: loop {
-            // 2. render the current board states for both players
-            let exit_option: std::io::Result<Option<(usize, usize)>> = layouts::game::main_loop::show_once(
-                &mut out,
-                self.turn_count,
-                &mut self.player_a, 
-                &mut self.player_b, 
-                &mut player_a_cursor_pos
-            );
-
-            match exit_option {
-                Ok(selected_indices) => {
-                    if let Some((row, col)) = selected_indices {
-                        // apply the guessed location to player B's board
-                        self.player_b.guess(row, col);
-
-                        // play the computer's turn
-                        self.player_a.auto_guess();
-
-                        // check for win condition
-                        if self.player_b.all_ships_sunk() {
-                            break 'gameLoop Ok(GameEndReason::PlayerAWon);
-                        } else if self.player_a.all_ships_sunk() {
-                            break 'gameLoop Ok(GameEndReason::PlayerBWon);
+            for seat in self.seat..self.players.len() {
+                self.seat = seat;
+
+                // players whose fleets are sunk are out of the game
+                if self.players[seat].all_ships_sunk() {
+                    continue;
+                }
+
+                let action: Action = if self.computers[seat].is_some() {
+                    let incoming: Option<arsenal::Shot> = self.incoming[seat];
+                    self.computer_action(seat, incoming.as_ref())
+                } else {
+                    // when humans share the computer, hide the boards until the next one is ready
+                    // leaving from there saves the game, the same as leaving during the turn
+                    if hot_seat {
+                        match layouts::game::pass_screen::show_in(&mut out, self.players[seat].get_name(), "save and quit") {
+                            Ok(true) => {},
+                            Ok(false) => break 'gameLoop Ok(GameEndReason::Saved),
+                            Err(e) => break 'gameLoop Err(e),
                         }
+                    }
+
+                    // render the boards from this player's point of view until they pick an action
+                    let action: Action = loop {
+                        match layouts::game::main_loop::show_once(
+                            &mut out,
+                            self.turn_count,
+                            &self.log,
+                            &toasts,
+                            &mut self.players,
+                            &self.tallies,
+                            seat,
+                            &mut targetings[seat]
+                        ) {
+                            Ok(Some(action)) => break action,
+                            Ok(None) => {}, // continue the render loop
+                            Err(e) => break 'gameLoop Err(e)  // exit the game loop with the error
+                        }
+                    };
+                    toasts.clear();
+                    action
+                };
+
+                // the game is saved at the start of this player's turn, so they get to take it when it's continued
+                if let Action::SaveAndQuit = action {
+                    break 'gameLoop Ok(GameEndReason::Saved);
+                }
 
-                        // increment turn count if no win
-                        self.turn_count += 1;
+                // take note of the board being fired at, to animate what the shot changes
+                let before: Option<(usize, animation::Before)> = match action {
+                    Action::Fire(target, shot) if tick.is_some() => Some((target, animation::Before::take(&self.players[target], &shot))),
+                    _ => None,
+                };
+
+                if let Action::Fire(target, shot) = action {
+                    self.incoming[target] = Some(shot);
+                }
+                self.apply(seat, action);
+
+                // play the shot to whoever is at the keyboard. when humans share the computer, a human
+                // who was fired at while someone else was playing is shown the shot on their next turn
+                if let Some((target, before)) = before
+                    && let Some(tick) = tick {
+                    let viewer: Option<usize> = match (self.computers[seat].is_none(), hot_seat) {
+                        (true, _) => Some(seat),
+                        (false, false) => self.human_seats().first().copied(),
+                        (false, true) => None,
+                    };
+                    let shown: animation::Animation = animation::Animation::new(target, before, &self.players[target], tick);
+                    if hot_seat && viewer != Some(target) && self.computers[target].is_none() {
+                        targetings[target].animations.push(shown.clone());
+                    }
+                    if let Some(viewer) = viewer {
+                        targetings[viewer].animations.push(shown);
+                        while !targetings[viewer].animations.is_empty() {
+                            if let Err(e) = layouts::game::main_loop::show_once(
+                                &mut out,
+                                self.turn_count,
+                                &self.log,
+                                &toasts,
+                                &mut self.players,
+                                &self.tallies,
+                                viewer,
+                                &mut targetings[viewer]
+                            ) {
+                                break 'gameLoop Err(e);
+                            }
+                        }
                     }
-                }, // continue the game loop
-                Err(e) => break 'gameLoop Err(e)  // exit the game loop with the error
-            };
-            
+                }
+
+                // check for win condition
+                let alive: Vec<usize> = self.alive_teams();
+                let winner: Option<usize> = match alive.len() {
+                    0 | 1 => Some(alive.first().copied().unwrap_or(self.rules.team_of(seat))),
+                    _ => None,
+                };
+
+                // look for achievements the human hasn't unlocked yet
+                if let Some((human, known)) = &unlocked {
+                    let won: bool = winner == Some(self.rules.team_of(*human));
+                    for achievement in achievements::earned(self, *human, won) {
+                        if !known.has(achievement) && !self.earned.contains(&achievement) {
+                            self.earned.push(achievement);
+                            toasts.push(achievement);
+                        }
+                    }
+                }
+
+                if let Some(winner) = winner {
+                    break 'gameLoop Ok(match self.rules.teams {
+                        true => GameEndReason::TeamWon(winner),
+                        false => GameEndReason::PlayerWon(winner),
+                    });
+                }
+            }
+
+            // increment turn count if no win
+            self.seat = 0;
+            self.turn_count += 1;
         };
 
+        // remove any leftover targeting highlights so the final boards are shown as they are
+        for player in self.players.iter_mut() {
+            player.clear_highlights();
+        }
+
+        // write the game to disk if the player asked to continue it later
+        if let Ok(GameEndReason::Saved) = main_loop_exit_option
+            && let Err(e) = storage::save_game(self) {
+            execute!(out, cursor::Show, event::DisableMouseCapture, terminal::LeaveAlternateScreen)?;
+            terminal::disable_raw_mode()?;
+            return Err(std::io::Error::other(e));
+        }
+
         // exit the alternate screen on game end
-        execute!(out, cursor::Show, terminal::LeaveAlternateScreen)?;
+        execute!(out, cursor::Show, event::DisableMouseCapture, terminal::LeaveAlternateScreen)?;
         terminal::disable_raw_mode()?;
 
         main_loop_exit_option
     }
-}
\ No newline at end of file
+}
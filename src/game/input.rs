@@ -0,0 +1,351 @@
+/// This module contains the mapping from key presses to the commands they stand for, so the keys
+/// can be changed without touching the screens that handle them. Typing text, like a name or a
+/// cell, always uses the keys as they are.
+use std::{fmt, vec};
+use crossterm::event;
+use serde::{Deserialize, Serialize};
+use crate::game::{settings, storage, themes};
+
+
+/// An enum defining every command that can be given with a key.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Command {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Rotate,
+    Flip,
+    CycleShip,
+    CycleWeapon,
+    SwitchTarget,
+    MoveShip,
+    AutoPlace,
+    SaveFormation,
+    LoadFormation,
+    SaveGame,
+    ScrollLogUp,
+    ScrollLogDown,
+    Fire,
+    ShowAchievements,
+    ResetStatistics,
+    DeleteProfile,
+    PlayPause,
+    JumpToStart,
+    JumpToEnd,
+    ExportReplay,
+    Yes,
+    Confirm,
+    Back,
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Command::MoveUp => write!(f, "move_up"),
+            Command::MoveDown => write!(f, "move_down"),
+            Command::MoveLeft => write!(f, "move_left"),
+            Command::MoveRight => write!(f, "move_right"),
+            Command::Rotate => write!(f, "rotate"),
+            Command::Flip => write!(f, "flip"),
+            Command::CycleShip => write!(f, "cycle_ship"),
+            Command::CycleWeapon => write!(f, "cycle_weapon"),
+            Command::SwitchTarget => write!(f, "switch_target"),
+            Command::MoveShip => write!(f, "move_ship"),
+            Command::AutoPlace => write!(f, "auto_place"),
+            Command::SaveFormation => write!(f, "save_formation"),
+            Command::LoadFormation => write!(f, "load_formation"),
+            Command::SaveGame => write!(f, "save_game"),
+            Command::ScrollLogUp => write!(f, "scroll_log_up"),
+            Command::ScrollLogDown => write!(f, "scroll_log_down"),
+            Command::Fire => write!(f, "fire"),
+            Command::ShowAchievements => write!(f, "show_achievements"),
+            Command::ResetStatistics => write!(f, "reset_statistics"),
+            Command::DeleteProfile => write!(f, "delete_profile"),
+            Command::PlayPause => write!(f, "play_pause"),
+            Command::JumpToStart => write!(f, "jump_to_start"),
+            Command::JumpToEnd => write!(f, "jump_to_end"),
+            Command::ExportReplay => write!(f, "export_replay"),
+            Command::Yes => write!(f, "yes"),
+            Command::Confirm => write!(f, "confirm"),
+            Command::Back => write!(f, "back"),
+        }
+    }
+}
+
+impl Command {
+    /// A static array containing every command to iterate over.
+    pub const ALL: [Command; 27] = [
+        Command::MoveUp,
+        Command::MoveDown,
+        Command::MoveLeft,
+        Command::MoveRight,
+        Command::Rotate,
+        Command::Flip,
+        Command::CycleShip,
+        Command::CycleWeapon,
+        Command::SwitchTarget,
+        Command::MoveShip,
+        Command::AutoPlace,
+        Command::SaveFormation,
+        Command::LoadFormation,
+        Command::SaveGame,
+        Command::ScrollLogUp,
+        Command::ScrollLogDown,
+        Command::Fire,
+        Command::ShowAchievements,
+        Command::ResetStatistics,
+        Command::DeleteProfile,
+        Command::PlayPause,
+        Command::JumpToStart,
+        Command::JumpToEnd,
+        Command::ExportReplay,
+        Command::Yes,
+        Command::Confirm,
+        Command::Back,
+    ];
+
+    /// Generate a consuming iterator over the commands
+    pub fn iter() -> impl Iterator<Item = Command> {
+        Self::ALL.into_iter()
+    }
+}
+
+/// The movement commands, in the order they are listed in the help text.
+pub const DIRECTIONS: [Command; 4] = [Command::MoveLeft, Command::MoveUp, Command::MoveRight, Command::MoveDown];
+
+/// The commands used in menus and other screens that only pick from a list.
+pub const MENU: [Command; 6] = [
+    Command::MoveUp, Command::MoveDown, Command::MoveLeft, Command::MoveRight,
+    Command::Confirm, Command::Back
+];
+
+/// The commands used in the profile menu, which only moves up and down its list.
+pub const PROFILES: [Command; 5] = [
+    Command::MoveUp, Command::MoveDown, Command::DeleteProfile, Command::Confirm, Command::Back
+];
+
+/// The commands used on the statistics screen.
+pub const STATISTICS: [Command; 3] = [Command::ShowAchievements, Command::ResetStatistics, Command::Back];
+
+/// The commands used while watching a replay.
+pub const REPLAY: [Command; 7] = [
+    Command::MoveLeft, Command::MoveRight, Command::PlayPause, Command::JumpToStart, Command::JumpToEnd,
+    Command::ExportReplay, Command::Back
+];
+
+/// The commands used while placing ships.
+pub const PLACING: [Command; 12] = [
+    Command::MoveUp, Command::MoveDown, Command::MoveLeft, Command::MoveRight,
+    Command::Rotate, Command::Flip, Command::CycleShip, Command::AutoPlace,
+    Command::SaveFormation, Command::LoadFormation, Command::Confirm, Command::Back
+];
+
+/// The commands used while aiming at an opponent's board.
+pub const FIRING: [Command; 13] = [
+    Command::MoveUp, Command::MoveDown, Command::MoveLeft, Command::MoveRight,
+    Command::Rotate, Command::CycleWeapon, Command::SwitchTarget, Command::MoveShip,
+    Command::SaveGame, Command::ScrollLogUp, Command::ScrollLogDown, Command::Fire, Command::Back
+];
+
+/// The commands used while moving one of the player's own ships.
+pub const MOVING: [Command; 11] = [
+    Command::MoveUp, Command::MoveDown, Command::MoveLeft, Command::MoveRight,
+    Command::Rotate, Command::CycleShip, Command::MoveShip, Command::ScrollLogUp, Command::ScrollLogDown,
+    Command::Confirm, Command::Back
+];
+
+/// Every screen along with the commands used on it. A key can only stand for one command per screen.
+const SCREENS: [(&str, &[Command]); 7] = [
+    ("in menus", &MENU),
+    ("in the profile menu", &PROFILES),
+    ("on the statistics screen", &STATISTICS),
+    ("while watching a replay", &REPLAY),
+    ("while placing ships", &PLACING),
+    ("while firing", &FIRING),
+    ("while moving a ship", &MOVING),
+];
+
+/// Parse the name of a key as written in the settings file, such as "up", "tab" or "k".
+pub fn parse_key(name: &str) -> Option<event::KeyCode> {
+    let code: event::KeyCode = match name.to_ascii_lowercase().as_str() {
+        "up" => event::KeyCode::Up,
+        "down" => event::KeyCode::Down,
+        "left" => event::KeyCode::Left,
+        "right" => event::KeyCode::Right,
+        "enter" => event::KeyCode::Enter,
+        "esc" => event::KeyCode::Esc,
+        "tab" => event::KeyCode::Tab,
+        "backspace" => event::KeyCode::Backspace,
+        "space" => event::KeyCode::Char(' '),
+        "home" => event::KeyCode::Home,
+        "end" => event::KeyCode::End,
+        "pageup" => event::KeyCode::PageUp,
+        "pagedown" => event::KeyCode::PageDown,
+        other => {
+            let mut chars = other.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if !c.is_whitespace() && !c.is_control() => event::KeyCode::Char(c),
+                _ => return None,
+            }
+        }
+    };
+    Some(code)
+}
+
+/// Get the label a key is shown with in the help text. The arrow keys are spelled out when
+/// only ASCII is drawn.
+fn key_label(code: event::KeyCode) -> String {
+    match code {
+        event::KeyCode::Up if themes::is_ascii() => "Up".to_string(),
+        event::KeyCode::Down if themes::is_ascii() => "Down".to_string(),
+        event::KeyCode::Left if themes::is_ascii() => "Left".to_string(),
+        event::KeyCode::Right if themes::is_ascii() => "Right".to_string(),
+        event::KeyCode::Up => "↑".to_string(),
+        event::KeyCode::Down => "↓".to_string(),
+        event::KeyCode::Left => "←".to_string(),
+        event::KeyCode::Right => "→".to_string(),
+        event::KeyCode::Enter => "Enter".to_string(),
+        event::KeyCode::Esc => "Esc".to_string(),
+        event::KeyCode::Tab => "Tab".to_string(),
+        event::KeyCode::Backspace => "Backspace".to_string(),
+        event::KeyCode::Home => "Home".to_string(),
+        event::KeyCode::End => "End".to_string(),
+        event::KeyCode::PageUp => "PageUp".to_string(),
+        event::KeyCode::PageDown => "PageDown".to_string(),
+        event::KeyCode::Char(' ') => "Space".to_string(),
+        event::KeyCode::Char(c) => c.to_uppercase().to_string(),
+        _ => "?".to_string(),
+    }
+}
+
+/// Get the keys a preset binds to a command, starting with the one shown in the help text. The
+/// letter presets keep the arrow keys working as well, and move the commands whose usual letter
+/// they take for moving around to another key.
+fn preset_keys(preset: settings::KeyPreset, command: Command) -> vec::Vec<event::KeyCode> {
+    use event::KeyCode::{Char, Down, End, Enter, Esc, Home, Left, PageDown, PageUp, Right, Tab, Up};
+    let letters: Option<[char; 4]> = match preset {
+        settings::KeyPreset::Arrows => None,
+        settings::KeyPreset::Vim => Some(['k', 'j', 'h', 'l']),
+        settings::KeyPreset::Wasd => Some(['w', 's', 'a', 'd']),
+    };
+    let moving = |arrow: event::KeyCode, i: usize| match letters {
+        Some(letters) => vec![Char(letters[i]), arrow],
+        None => vec![arrow],
+    };
+
+    match (command, preset) {
+        (Command::MoveUp, _) => moving(Up, 0),
+        (Command::MoveDown, _) => moving(Down, 1),
+        (Command::MoveLeft, _) => moving(Left, 2),
+        (Command::MoveRight, _) => moving(Right, 3),
+        (Command::Rotate, _) => vec![Char('r')],
+        (Command::Flip, _) => vec![Char('f')],
+        (Command::CycleShip | Command::CycleWeapon, _) => vec![Tab],
+        (Command::SwitchTarget, _) => vec![Char('t')],
+        (Command::MoveShip, _) => vec![Char('m')],
+        (Command::AutoPlace, settings::KeyPreset::Wasd) => vec![Char('p')],
+        (Command::AutoPlace, _) => vec![Char('a')],
+        (Command::SaveFormation, settings::KeyPreset::Wasd) => vec![Char('k')],
+        (Command::SaveFormation, _) => vec![Char('s')],
+        (Command::LoadFormation, settings::KeyPreset::Vim) => vec![Char('o')],
+        (Command::LoadFormation, _) => vec![Char('l')],
+        (Command::SaveGame, settings::KeyPreset::Wasd) => vec![Char('q')],
+        (Command::SaveGame, _) => vec![Char('s')],
+        (Command::ScrollLogUp, _) => vec![PageUp],
+        (Command::ScrollLogDown, _) => vec![PageDown],
+        (Command::ShowAchievements, _) => vec![Char('a')],
+        (Command::ResetStatistics, _) => vec![Char('r')],
+        (Command::DeleteProfile, _) => vec![Char('d')],
+        (Command::PlayPause, _) => vec![Char(' ')],
+        (Command::JumpToStart, _) => vec![Home],
+        (Command::JumpToEnd, _) => vec![End],
+        (Command::ExportReplay, _) => vec![Char('x')],
+        (Command::Yes, _) => vec![Char('y')],
+        (Command::Fire | Command::Confirm, _) => vec![Enter],
+        (Command::Back, _) => vec![Esc],
+    }
+}
+
+/// Returns true if two keys are the same. Letters match either case, so caps lock doesn't get in the way.
+fn same_key(a: event::KeyCode, b: event::KeyCode) -> bool {
+    match (a, b) {
+        (event::KeyCode::Char(a), event::KeyCode::Char(b)) => a.eq_ignore_ascii_case(&b),
+        _ => a == b,
+    }
+}
+
+/// A struct holding the keys bound to every command.
+#[derive(Clone)]
+pub struct KeyMap {
+    bindings: vec::Vec<(Command, vec::Vec<event::KeyCode>)>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self::new(&settings::KeySettings::default())
+    }
+}
+
+impl KeyMap {
+    /// Create the key map for the given key settings. Overridden commands only use the keys they
+    /// are given, leaving out the names that aren't keys.
+    pub fn new(keys: &settings::KeySettings) -> Self {
+        let bindings = Command::iter()
+            .map(|command| match keys.bindings.get(&command) {
+                Some(names) => (command, names.split_whitespace().filter_map(parse_key).collect()),
+                None => (command, preset_keys(keys.preset, command)),
+            })
+            .collect();
+        Self { bindings }
+    }
+
+    /// Create the key map from the settings file, using the default keys if it can't be read.
+    pub fn load() -> Self {
+        Self::new(&storage::load_settings().unwrap_or_default().keys)
+    }
+
+    /// Get the keys bound to a command.
+    pub fn get_keys(&self, command: Command) -> &[event::KeyCode] {
+        self.bindings.iter().find(|(other, _)| *other == command).map_or(&[], |(_, keys)| keys)
+    }
+
+    /// Returns true if the key stands for any of the given commands.
+    pub fn is_bound(&self, code: event::KeyCode, commands: &[Command]) -> bool {
+        commands.iter().any(|command| self.get_keys(*command).iter().any(|bound| same_key(*bound, code)))
+    }
+
+    /// Find which of the given commands a key press stands for, if any.
+    pub fn command(&self, key: &event::KeyEvent, commands: &[Command]) -> Option<Command> {
+        commands.iter().copied().find(|command| self.get_keys(*command).iter().any(|bound| same_key(*bound, key.code)))
+    }
+
+    /// Get the label of the key shown for a command in the help text.
+    pub fn label(&self, command: Command) -> String {
+        self.get_keys(command).first().map_or("(unbound)".to_string(), |code| key_label(*code))
+    }
+
+    /// Get the labels of the keys shown for several commands, joined by slashes, e.g. "←/→".
+    pub fn labels(&self, commands: &[Command]) -> String {
+        commands.iter().map(|command| self.label(*command)).collect::<vec::Vec<String>>().join("/")
+    }
+
+    /// Describe every key that stands for more than one command on the same screen.
+    pub fn conflicts(&self) -> vec::Vec<String> {
+        let mut conflicts: vec::Vec<String> = vec![];
+        for (screen, commands) in SCREENS {
+            for (i, first) in commands.iter().enumerate() {
+                for second in commands[i + 1..].iter() {
+                    for code in self.get_keys(*first) {
+                        let conflict: String = format!("{} is bound to both {} and {} {}", key_label(*code), first, second, screen);
+                        if self.get_keys(*second).iter().any(|other| same_key(*code, *other)) && !conflicts.contains(&conflict) {
+                            conflicts.push(conflict);
+                        }
+                    }
+                }
+            }
+        }
+        conflicts
+    }
+}
@@ -10,12 +10,81 @@ use crossterm::{
     style,
     execute, queue
 };
+use crate::game::{animation, input, log, render, themes};
 
 /// Trait for defining each terminal layout module.
 pub trait TerminalLayout<T> {
     fn show() -> std::io::Result<T>;
 }
 
+/// Get the size of the terminal in columns and rows, assuming the usual 80 by 24 if it can't be read.
+fn terminal_size() -> (u16, u16) {
+    terminal::size().unwrap_or((80, 24))
+}
+
+/// Split text into lines that fit in the given width, breaking it between words. A word too long
+/// for a line of its own is left to be cut off at the edge of the terminal.
+fn wrap(text: &str, width: u16) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    let mut line: String = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width as usize {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Lay out labels left to right, starting a new line once a line is full. Every label takes up at
+/// least the given number of columns, so that short labels line up. Returns the position of each
+/// label, relative to the first one.
+fn flow(labels: &[String], column: u16, width: u16) -> Vec<(u16, u16)> {
+    let mut positions: Vec<(u16, u16)> = vec![];
+    let (mut x, mut y) = (0, 0);
+    for label in labels {
+        let length: u16 = label.chars().count() as u16;
+        if x > 0 && x + length > width {
+            (x, y) = (0, y + 1);
+        }
+        positions.push((x, y));
+        x += (length + 1).max(column);
+    }
+    positions
+}
+
+/// Returns true if the terminal is smaller than the given size in columns and rows.
+fn too_small(needed: (u16, u16)) -> bool {
+    let (width, height) = terminal_size();
+    width < needed.0 || height < needed.1
+}
+
+/// Tell the user the terminal is too small for the screen, and wait for something to happen, such
+/// as the terminal being resized. Returns true if the user pressed the back key to leave instead.
+fn wait_for_room(out: &mut render::Renderer, needed: (u16, u16), keys: &input::KeyMap) -> std::io::Result<bool> {
+    let (width, height) = terminal_size();
+    let message: String = format!(
+        "The terminal is too small for this screen. At {} columns wide it needs {} rows, and it can't be narrower than {} columns (it is {}x{} now). Make it bigger to carry on, or press {} to leave.",
+        width, needed.1, needed.0, width, height, keys.label(input::Command::Back)
+    );
+    queue!(out, cursor::MoveTo(0, 0), terminal::Clear(terminal::ClearType::All))?;
+    for (i, line) in wrap(&message, width).iter().enumerate() {
+        queue!(out, cursor::MoveTo(0, i as u16), style::Print(line))?;
+    }
+    out.flush()?;
+
+    Ok(matches!(
+        event::read()?,
+        event::Event::Key(key) if key.kind == event::KeyEventKind::Press && keys.command(&key, &[input::Command::Back]).is_some()
+    ))
+}
+
 /// This module contains logic for managing states of menus.
 pub mod menus {
     use super::*;
@@ -23,23 +92,135 @@ pub mod menus {
     /// Static reference to the title, stored in "title.txt"
     const TITLE: &str = include_str!("title.txt");
 
+    /// The narrowest terminal a menu is drawn in.
+    const MIN_MENU_WIDTH: u16 = 20;
+
+    /// Get the help text for moving through a list with the bound keys, ending with what the back key does.
+    fn navigation_help(back: &str) -> String {
+        let keys: input::KeyMap = input::KeyMap::load();
+        format!("Use {} to move, {} to {}", keys.labels(&[input::Command::MoveUp, input::Command::MoveDown]), keys.label(input::Command::Back), back)
+    }
+
+    /// Render a list of options below the title and let the user pick one with the arrow keys, starting
+    /// from the given option. Returns the index of the selected option, or None if the user backed out with Esc.
+    fn select_option<T: fmt::Display>(options: &[T], help: &str, initial: usize) -> std::io::Result<Option<usize>> {
+        let title_width: u16 = TITLE.lines().map(|line| line.chars().count()).max().unwrap_or(0) as u16;
+        let title_height: u16 = TITLE.lines().count() as u16 + 2;
+
+        // enter an alternate screen for the menu
+        terminal::enable_raw_mode()?;
+        let mut out = render::Renderer::stdout();
+        execute!(out, terminal::EnterAlternateScreen, cursor::Hide, terminal::Clear(terminal::ClearType::All))?;
+        let keys: input::KeyMap = input::KeyMap::load();
+
+        // begin rendering loop. at the end of this loop, we get returned the option that
+        // the user selected that we can use to move to another screen in the layout
+        let mut selected: usize = initial.min(options.len().saturating_sub(1));
+        let selection: Option<usize> = 'render: loop {
+            // the title is left out when there isn't room for it, while the options can't do without
+            // the help above them. options too long for the terminal are cut off at its edge
+            let (width, height) = terminal_size();
+            let help_lines: Vec<String> = wrap(help, width);
+            let needed: (u16, u16) = (MIN_MENU_WIDTH, help_lines.len() as u16 + 1 + options.len() as u16);
+            if too_small(needed) {
+                if wait_for_room(&mut out, needed, &keys)? {
+                    break 'render None;
+                }
+                continue;
+            }
+            let title_shown: bool = width >= title_width && height >= title_height + needed.1;
+
+            // clear terminal and print the title in red, followed by the movement commands
+            queue!(out, cursor::MoveTo(0, 0), terminal::Clear(terminal::ClearType::All))?;
+            let mut top: u16 = 0;
+            if title_shown {
+                for line in TITLE.lines() {
+                    queue!(out, cursor::MoveTo(0, top), style::Print(line.red()))?;
+                    top += 1;
+                }
+                top += 2;
+            }
+            for line in help_lines.iter() {
+                queue!(out, cursor::MoveTo(0, top), style::Print(line))?;
+                top += 1;
+            }
+            top += 1;
+
+            // enumerate over the menu options and display each
+            for (i, option) in options.iter().enumerate() {
+                // if the current selected item is the one we're iterating over,
+                // apply a reverse highlight to that element to indicate to the user
+                // that they have selected this
+                if i == selected {
+                    queue!(out, style::SetAttribute(style::Attribute::Reverse))?;
+                }
+
+                // print a right facing arrow on the selected option. print each options's text
+                let line: String = format!(" {} {}", if i == selected { ">" } else { " " }, option);
+                queue!(out, cursor::MoveTo(0, top + i as u16), style::Print(line.chars().take(width as usize).collect::<String>()))?;
+
+                // if we just highlighted the selected text, we need to undo this highlight for
+                // the text below, so we add a no-reverse highlight after
+                if i == selected {
+                    queue!(out, style::SetAttribute(style::Attribute::NoReverse))?;
+                }
+            }
+
+            // write all output to the screen
+            out.flush()?;
+
+            // poll for the last event that occurred
+            if let event::Event::Key(key) = event::read()?
+                && key.kind == event::KeyEventKind::Press {
+                match keys.command(&key, &input::MENU) {
+                    // pressing up or down cycles back to the other end of the menu while navigating.
+                    Some(input::Command::MoveUp) => selected = (selected + options.len() - 1) % options.len(),
+                    Some(input::Command::MoveDown) => selected = (selected + 1) % options.len(),
+
+                    // get the menu option selected by the user and return it
+                    Some(input::Command::Confirm) => break 'render Some(selected),
+
+                    // back out of the menu if the user hits Esc
+                    Some(input::Command::Back) => break 'render None,
+                    _ => {}
+                }
+            }
+        };
+
+        // leave the menu screen.
+        execute!(out, cursor::Show, terminal::LeaveAlternateScreen)?;
+        terminal::disable_raw_mode()?;
+
+        // return an Ok with the selected menu option
+        Ok(selection)
+    }
+
     /// Module for displaying the main menu.
     pub mod main_menu {
         use super::*;
+        use crate::game::storage;
 
         /// An enum defining all possible menu options.
         #[derive(Clone)]
         pub enum MainMenuOptions {
+            Continue,
             NewGame,
             Statistics,
+            Replays,
+            Profiles,
+            Settings,
             Quit
         }
 
         impl fmt::Display for MainMenuOptions {
             fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
                 match self {
+                    MainMenuOptions::Continue => write!(f, "Continue"),
                     MainMenuOptions::NewGame => write!(f, "New Game"),
                     MainMenuOptions::Statistics => write!(f, "Statistics"),
+                    MainMenuOptions::Replays => write!(f, "Replays"),
+                    MainMenuOptions::Profiles => write!(f, "Profiles"),
+                    MainMenuOptions::Settings => write!(f, "Settings"),
                     MainMenuOptions::Quit => write!(f, "Quit")
                 }
             }
@@ -47,9 +228,13 @@ pub mod menus {
 
         impl MainMenuOptions {
             /// A static array containing all possible menu options to iterate over.
-            const ALL: [MainMenuOptions; 3] = [
+            const ALL: [MainMenuOptions; 7] = [
+                MainMenuOptions::Continue,
                 MainMenuOptions::NewGame,
                 MainMenuOptions::Statistics,
+                MainMenuOptions::Replays,
+                MainMenuOptions::Profiles,
+                MainMenuOptions::Settings,
                 MainMenuOptions::Quit
             ];
 
@@ -64,72 +249,22 @@ pub mod menus {
         impl TerminalLayout<MainMenuOptions> for MainMenu {
             /// Display the main menu in the terminal.
             fn show() -> std::io::Result<MainMenuOptions> {
-                // color the title string for the menu
-                let title: colored::ColoredString = format!("{}\n\n", TITLE).red();
-
-                // enter an alternate screen for the main menu
-                terminal::enable_raw_mode()?;
-                let mut out = std::io::stdout();
-                execute!(out, terminal::EnterAlternateScreen, cursor::Hide, terminal::Clear(terminal::ClearType::All))?;
-
-                // begin rendering loop. at the end of this loop, we get returned an option that
-                // the user selected that we can use to move to another screen in the layout
-                let mut selected: usize = 0;
-                let selection: MainMenuOptions = 'render: loop {
-                    // clear terminal and print the title and movement commands
-                    queue!(out, cursor::MoveTo(0, 0), terminal::Clear(terminal::ClearType::All))?;
-                    queue!(out, style::Print(&title))?;
-                    queue!(out, style::Print("Use ↑/↓ to move, Esc to exit\n\n"))?;
-
-                    // enumerate over the menu options and display each
-                    for (i, option) in MainMenuOptions::iter().enumerate() {
-                        // if the current selected item is the one we're iterating over,
-                        // apply a reverse highlight to that element to indicate to the user
-                        // that they have selected this
-                        if i == selected {
-                            queue!(out, style::SetAttribute(style::Attribute::Reverse))?;
-                        }
-
-                        // print a right facing arrow on the selected option. print each options's text
-                        queue!(out, style::Print(format!(" {} {}\n", if i == selected { ">" } else { " " }, option)))?;
-
-                        // if we just highlighted the selected text, we need to undo this highlight for
-                        // the text below, so we add a no-reverse highlight after
-                        if i == selected {
-                            queue!(out, style::SetAttribute(style::Attribute::NoReverse))?;
-                        }
-                    }
-
-                    // write all output to the screen
-                    out.flush()?;
-
-                    // poll for the last event that occurred
-                    if let event::Event::Key(key) = event::read()? {
-                        if key.kind == event::KeyEventKind::Press {
-                            match key.code {
-                                // rem_euclid always returns a positive int, so it handles negatives natively.
-                                // with this logic, pressing up or down cycles back to the other end of the menu
-                                // while navigating.
-                                event::KeyCode::Up => selected = (selected - 1).rem_euclid(MainMenuOptions::ALL.len()),
-                                event::KeyCode::Down => selected = (selected + 1) % MainMenuOptions::ALL.len(),
-
-                                // get the menu option selected by the user and return it
-                                event::KeyCode::Enter => break 'render MainMenuOptions::ALL[selected].clone(),
-
-                                // quit game if the user hits Esc
-                                event::KeyCode::Esc => break 'render MainMenuOptions::Quit,
-                                _ => {}
-                            }
-                        }
-                    }          
+                // only offer to continue if there is a saved game
+                let options: Vec<MainMenuOptions> = MainMenuOptions::iter()
+                    .filter(|option| !matches!(option, MainMenuOptions::Continue) || storage::has_saved_game())
+                    .collect();
+
+                // remind the user who they are playing as
+                let help: String = match storage::active_profile() {
+                    Some(name) => format!("Playing as {}. {}", name, navigation_help("exit")),
+                    None => navigation_help("exit"),
                 };
 
-                // leave the main menu screen.
-                execute!(out, cursor::Show, terminal::LeaveAlternateScreen)?;
-                terminal::disable_raw_mode()?;
-
-                // return an Ok with the selected menu option
-                Ok(selection)
+                // quit game if the user hits Esc
+                Ok(match select_option(&options, &help, 0)? {
+                    Some(i) => options[i].clone(),
+                    None => MainMenuOptions::Quit
+                })
             }
         }
     }
@@ -142,6 +277,9 @@ pub mod menus {
         #[derive(Clone)]
         pub enum NewGameMenuOptions {
             PlayComputer,
+            PlayPlayer,
+            FreeForAll,
+            TeamPlay,
             JoinGame,
             HostGame,
             Back
@@ -151,6 +289,9 @@ pub mod menus {
             fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
                 match self {
                     NewGameMenuOptions::PlayComputer => write!(f, "Player against Computer"),
+                    NewGameMenuOptions::PlayPlayer => write!(f, "Player vs Player (same computer)"),
+                    NewGameMenuOptions::FreeForAll => write!(f, "Free-for-all (3-4 players)"),
+                    NewGameMenuOptions::TeamPlay => write!(f, "Team play (2v2)"),
                     NewGameMenuOptions::JoinGame => write!(f, "Join Game"),
                     NewGameMenuOptions::HostGame => write!(f, "Host Game"),
                     NewGameMenuOptions::Back => write!(f, "Back")
@@ -160,8 +301,11 @@ pub mod menus {
 
         impl NewGameMenuOptions {
             /// A static array containing all possible menu options to iterate over.
-            const ALL: [NewGameMenuOptions; 4] = [
+            const ALL: [NewGameMenuOptions; 7] = [
                 NewGameMenuOptions::PlayComputer,
+                NewGameMenuOptions::PlayPlayer,
+                NewGameMenuOptions::FreeForAll,
+                NewGameMenuOptions::TeamPlay,
                 NewGameMenuOptions::JoinGame,
                 NewGameMenuOptions::HostGame,
                 NewGameMenuOptions::Back
@@ -178,213 +322,263 @@ pub mod menus {
         impl TerminalLayout<NewGameMenuOptions> for NewGameMenu {
             /// Display the new game menu in the terminal.
             fn show() -> std::io::Result<NewGameMenuOptions> {
-                // color the title string for the menu
-                let title: colored::ColoredString = format!("{}\n\n", TITLE).red();
-
-                // enter an alternate screen for menu
-                terminal::enable_raw_mode()?;
-                let mut out = std::io::stdout();
-                execute!(out, terminal::EnterAlternateScreen, cursor::Hide, terminal::Clear(terminal::ClearType::All))?;
-
-                // begin rendering loop. at the end of this loop, we get returned an option that
-                // the user selected that we can use to move to another screen in the layout
-                let mut selected: usize = 0;
-                let selection: NewGameMenuOptions = 'render: loop {
-                    // clear terminal and print the title and movement commands
-                    queue!(out, cursor::MoveTo(0, 0), terminal::Clear(terminal::ClearType::All))?;
-                    queue!(out, style::Print(&title))?;
-                    queue!(out, style::Print("Use ↑/↓ to move, Esc to go back\n\n"))?;
-
-                    // enumerate over the menu options and display each
-                    for (i, option) in NewGameMenuOptions::iter().enumerate() {
-                        // if the current selected item is the one we're iterating over,
-                        // apply a reverse highlight to that element to indicate to the user
-                        // that they have selected this
-                        if i == selected {
-                            queue!(out, style::SetAttribute(style::Attribute::Reverse))?;
-                        }
-
-                        // print a right facing arrow on the selected option. print each options's text
-                        queue!(out, style::Print(format!(" {} {}\n", if i == selected { ">" } else { " " }, option)))?;
-
-                        // if we just highlighted the selected text, we need to undo this highlight for
-                        // the text below, so we add a no-reverse highlight after
-                        if i == selected {
-                            queue!(out, style::SetAttribute(style::Attribute::NoReverse))?;
-                        }
-                    }
-
-                    // write all output to the screen
-                    out.flush()?;
-
-                    // poll for the last event that occurred
-                    if let event::Event::Key(key) = event::read()? {
-                        if key.kind == event::KeyEventKind::Press {
-                            match key.code {
-                                // rem_euclid always returns a positive int, so it handles negatives natively.
-                                // with this logic, pressing up or down cycles back to the other end of the menu
-                                // while navigating.
-                                event::KeyCode::Up => selected = (selected - 1).rem_euclid(NewGameMenuOptions::ALL.len()),
-                                event::KeyCode::Down => selected = (selected + 1) % NewGameMenuOptions::ALL.len(),
-
-                                // get the menu option selected by the user and return it
-                                event::KeyCode::Enter => break 'render NewGameMenuOptions::ALL[selected].clone(),
-
-                                // quit game if the user hits Esc
-                                event::KeyCode::Esc => break 'render NewGameMenuOptions::Back,
-                                _ => {}
-                            }
-                        }
-                    }          
-                };
-
-                // leave the main menu screen.
-                execute!(out, cursor::Show, terminal::LeaveAlternateScreen)?;
-                terminal::disable_raw_mode()?;
-
-                // return an Ok with the selected menu option
-                Ok(selection)
+                // go back if the user hits Esc
+                Ok(match select_option(&NewGameMenuOptions::ALL, &navigation_help("go back"), 0)? {
+                    Some(i) => NewGameMenuOptions::ALL[i].clone(),
+                    None => NewGameMenuOptions::Back
+                })
             }
         }
     }
-    
-}
-
-pub mod game {
-    use super::*;
-    use crate::game::components::{board, player, ship};
 
-    pub mod board_setup {
-        use std::vec;
+    /// Module for displaying the game mode menu. Returns None if the user goes back.
+    pub mod game_mode_menu {
+        use super::*;
+        use crate::game::{rules, storage};
+
+        pub struct GameModeMenu;
+
+        impl TerminalLayout<Option<rules::GameMode>> for GameModeMenu {
+            /// Display the game mode menu in the terminal, starting from the default mode in the settings.
+            fn show() -> std::io::Result<Option<rules::GameMode>> {
+                let options: Vec<String> = rules::GameMode::iter()
+                    .map(|mode| format!("{:<14} {}", mode, mode.description()))
+                    .collect();
+                let default: rules::GameMode = storage::load_settings().unwrap_or_default().rules.mode;
+                let initial: usize = rules::GameMode::iter().position(|mode| mode == default).unwrap_or(0);
+                Ok(select_option(&options, &format!("Choose a game mode. {}", navigation_help("go back")), initial)?
+                    .map(|i| rules::GameMode::ALL[i]))
+            }
+        }
+    }
 
+    /// Module for displaying the computer difficulty menu. Returns None if the user goes back.
+    pub mod difficulty_menu {
         use super::*;
+        use crate::game::components::ai;
+        use crate::game::storage;
+
+        pub struct DifficultyMenu;
+
+        impl TerminalLayout<Option<ai::Difficulty>> for DifficultyMenu {
+            /// Display the difficulty menu in the terminal, starting from the default difficulty in the settings.
+            fn show() -> std::io::Result<Option<ai::Difficulty>> {
+                let default: ai::Difficulty = storage::load_settings().unwrap_or_default().rules.difficulty;
+                let initial: usize = ai::Difficulty::iter().position(|difficulty| difficulty == default).unwrap_or(0);
+                Ok(select_option(&ai::Difficulty::ALL, &format!("Choose the computer's difficulty. {}", navigation_help("go back")), initial)?
+                    .map(|i| ai::Difficulty::ALL[i]))
+            }
+        }
+    }
 
-        /// An Enum defining the possible states that can be returned from the render loop.
-        pub enum ShipSetupOption {
-            Continue,
-            Quit
+    /// Module for displaying the list of saved replays.
+    pub mod replay_menu {
+        use super::*;
+        use crate::game::replay;
+
+        /// Display the saved replays in the terminal. Returns the index of the one
+        /// to watch, or None if the user goes back.
+        pub fn show(replays: &[replay::Replay]) -> std::io::Result<Option<usize>> {
+            let options: Vec<String> = match replays.is_empty() {
+                true => vec!["No replays yet. Finish a game to record one".to_string()],
+                false => replays.iter().map(|replay| replay.summary()).collect(),
+            };
+            let selection: Option<usize> = select_option(&options, &format!("Choose a replay to watch. {}", navigation_help("go back")), 0)?;
+            Ok(selection.filter(|_| !replays.is_empty()))
         }
+    }
 
-        /// Try to place a ship on the player's board. If successful, returns true. Otherwise, returns false.
-        fn get_ship_placement_cell_states(
-            ship_type: &ship::ShipType, 
-            orientation: &ship::ShipOrientation,
-            selected_cell: &(usize, usize),
-        ) -> (vec::Vec<(usize, usize)>, board::CellState) {
-            // clone the selected cell so we can modify it internally
-            let mut current = selected_cell.clone();
+    /// Module for displaying the player's statistics.
+    pub mod statistics_screen {
+        use super::*;
+        use crate::game::{achievements, replay, stats};
 
-            // initialize vector for indices and cell state that will be rendered
-            let mut indices: vec::Vec<(usize, usize)> = vec![];
-            let mut state: board::CellState = board::CellState::Highlighted;
+        /// Format a share from 0 to 1 as a percentage, or a dash if there is nothing to show yet.
+        fn percent(share: Option<f64>) -> String {
+            share.map_or("-".to_string(), |share| format!("{:.1}%", share * 100.0))
+        }
 
-            // loop over the size of this ship and match the orientation to determine
-            // which cells to try to highlight
-            for _ in 0..ship_type.size() {
-                indices.push(current);
-                match orientation {
-                    ship::ShipOrientation::Left => {
-                        if current.1 > 0 { current.1 -= 1; } else { 
-                            current.1 = board::COLS - 1;
-                            state = board::CellState::InvalidPlacement;
-                            break;
-                        };
-                    },
-                    ship::ShipOrientation::Right => {
-                        if current.1 < board::COLS { current.1 += 1; } else { 
-                            current.1 = 0;
-                            state = board::CellState::InvalidPlacement;
-                            break;
-                        }
-                    },
-                    ship::ShipOrientation::Up => {
-                        if current.0 > 0 { current.0 -= 1; } else { 
-                            current.0 = board::ROWS - 1;
-                            state = board::CellState::InvalidPlacement;
-                            break;
-                        }
-                    },
-                    ship::ShipOrientation::Down => {
-                        if current.0 < board::ROWS  { current.0 += 1; } else { 
-                            current.0 = 0;
-                            state = board::CellState::InvalidPlacement;
-                            break;
-                        }
-                    },
-                }
-            };
-            (indices, state)
+        /// List every achievement, marking the ones that have been unlocked and when.
+        fn achievement_lines(unlocked: &achievements::Achievements) -> Vec<String> {
+            let mut lines: Vec<String> = vec![format!("Achievements ({}/{})", unlocked.get_unlocked().len(), achievements::Achievement::ALL.len()), String::new()];
+            for achievement in achievements::Achievement::iter() {
+                let (mark, when) = match unlocked.unlocked_at(achievement) {
+                    Some(time) => ("[x]", format!("  unlocked {}", replay::format_time(time))),
+                    None => ("[ ]", String::new()),
+                };
+                lines.push(format!("{} {:<18} {}{}", mark, achievement.to_string(), achievement.description(), when));
+            }
+            lines
         }
-        
-        /// Display the board setup in the terminal.
-        pub fn show(player: &mut player::Player) -> std::io::Result<ShipSetupOption> {
-            // enter an alternate screen
+
+        /// Display the statistics and rating of the named profile in the terminal until the user goes back.
+        /// The user can switch to the profile's achievements, and can also reset the statistics from here,
+        /// after confirming. Returns true if they were reset.
+        pub fn show(
+            name: &str,
+            rating: i32,
+            statistics: &mut stats::Statistics,
+            unlocked: &achievements::Achievements
+        ) -> std::io::Result<bool> {
+            // enter an alternate screen for the statistics
             terminal::enable_raw_mode()?;
-            let mut out = std::io::stdout();
+            let mut out = render::Renderer::stdout();
             execute!(out, terminal::EnterAlternateScreen, cursor::Hide, terminal::Clear(terminal::ClearType::All))?;
 
-            // set the necessary values for tracking the ship placement state
-            let mut selected: (usize, usize) = (0, 0);
-            let mut ship_selection: usize = 0;
-            let mut ship_has_been_placed: vec::Vec<bool> = vec![false; ship::ShipType::ALL.len()];
-            let mut selected_ship_type: ship::ShipType = ship::ShipType::ALL[ship_selection];
-            let mut cell_indices: vec::Vec<(usize, usize)>;
-            let mut ship_orientation: ship::ShipOrientation = ship::ShipOrientation::Left;
-            let mut cell_state_type: board::CellState;
+            let keys: input::KeyMap = input::KeyMap::load();
+            let mut confirming: bool = false;
+            let mut reset: bool = false;
+            let mut showing_achievements: bool = false;
+            loop {
+                let win_rate: Option<f64> = match statistics.games_played() {
+                    0 => None,
+                    played => Some(statistics.wins() as f64 / played as f64),
+                };
+                let mut lines: Vec<String> = vec![
+                    match (confirming, showing_achievements) {
+                        (true, _) => format!("Reset all statistics? Press {} to confirm, any other key to cancel", keys.label(input::Command::Yes)),
+                        (false, true) => format!(
+                            "Press {} to go back to the statistics, {} to go back",
+                            keys.label(input::Command::ShowAchievements),
+                            keys.label(input::Command::Back)
+                        ),
+                        (false, false) => format!(
+                            "Press {} to see your achievements, {} to reset the statistics, {} to go back",
+                            keys.label(input::Command::ShowAchievements),
+                            keys.label(input::Command::ResetStatistics),
+                            keys.label(input::Command::Back)
+                        ),
+                    },
+                    String::new(),
+                    format!("Statistics for {}", name),
+                    String::new(),
+                    format!("Rating:                {}", rating),
+                    format!("Games played:          {}", statistics.games_played()),
+                    format!("Wins / losses:         {} / {} ({})", statistics.wins(), statistics.losses(), percent(win_rate)),
+                    format!("Accuracy:              {}", percent(statistics.accuracy())),
+                    format!("Average shots to win:  {}", statistics.average_shots_to_win().map_or("-".to_string(), |shots| format!("{:.1}", shots))),
+                    format!("Longest win streak:    {} (current {})", statistics.longest_streak(), statistics.current_streak()),
+                    format!("Fastest win:           {}", statistics.fastest_win().map_or("-".to_string(), |turns| format!("{} turns", turns))),
+                    String::new(),
+                    format!("{:<14} {:<12} {:>5} {:>7}", "Mode", "Difficulty", "Wins", "Losses"),
+                ];
+                for record in statistics.get_records() {
+                    let difficulty: String = record.difficulty.map_or("-".to_string(), |difficulty| difficulty.to_string());
+                    lines.push(format!("{:<14} {:<12} {:>5} {:>7}", record.mode.to_string(), difficulty, record.wins, record.losses));
+                }
+                if showing_achievements {
+                    lines.truncate(2);
+                    lines.extend(achievement_lines(unlocked));
+                }
 
-            // begin rendering loop. at the end of this loop, we get returned an option that
-            // the user has completed setting up and that the game is ready to progress to
-            // the next stage
-            let selected_ship_setup_option: ShipSetupOption = 'render: loop {
-                // clear terminal and print the title and movement commands
+                // clear terminal and print every line of the statistics
                 queue!(out, cursor::MoveTo(0, 0), terminal::Clear(terminal::ClearType::All))?;
-                queue!(out, style::Print("Use ←/↑/→/↓ to move, R to rotate the ship's orientation, Esc to quit the game\n\n"))?;
-                
-                for (i, ship) in ship::ShipType::iter().enumerate() {
-                    // highlight the currently selected ship
-                    if i == ship_selection {
-                        queue!(out, style::SetAttribute(style::Attribute::Reverse))?;
-                    }
-                    // if the ship has been placed, gray it out
-                    if ship_has_been_placed[i] {
-                        queue!(out, style::SetForegroundColor(style::Color::DarkGrey))?;
-                    }
-                    
-                    queue!(out, cursor::MoveTo(i as u16 * 15, 2), style::Print(ship))?;
+                for (i, line) in lines.iter().enumerate() {
+                    queue!(out, cursor::MoveTo(0, i as u16), style::Print(line))?;
+                }
 
-                    // reset styles after printing
-                    if ship_has_been_placed[i] {
-                        queue!(out, style::SetForegroundColor(style::Color::Reset))?;
+                // write all output to the screen
+                out.flush()?;
+
+                // poll for the last event that occurred
+                if let event::Event::Key(key) = event::read()?
+                    && key.kind == event::KeyEventKind::Press {
+                    if confirming {
+                        if keys.command(&key, &[input::Command::Yes]).is_some() {
+                            statistics.reset();
+                            reset = true;
+                        }
+                        confirming = false;
+                        continue;
                     }
-                    if i == ship_selection {
-                        queue!(out, style::SetAttribute(style::Attribute::NoReverse))?;
+                    match keys.command(&key, &input::STATISTICS) {
+                        Some(input::Command::Back) => break,
+                        Some(input::Command::ShowAchievements) => showing_achievements = !showing_achievements,
+                        Some(input::Command::ResetStatistics) if !showing_achievements => confirming = true,
+                        _ => {}
                     }
                 }
+            }
 
-                if ship_has_been_placed.iter().all(|x| x == &true) {
-                    queue!(out, cursor::MoveTo(ship::ShipType::ALL.len() as u16 * 15, 2), style::Print("Press Enter to Continue"))?;
-                }
+            // leave the statistics screen.
+            execute!(out, cursor::Show, terminal::LeaveAlternateScreen)?;
+            terminal::disable_raw_mode()?;
 
-                // find the ship that corresponds to the currently selected index
-                selected_ship_type = ship::ShipType::ALL[ship_selection];
-                (cell_indices, cell_state_type) = get_ship_placement_cell_states(&selected_ship_type, &ship_orientation, &selected);
+            Ok(reset)
+        }
+    }
 
-                // print each cell in the board
-                for r in 0..board::ROWS {
-                    for c in 0..board::COLS {
+    /// Module for displaying the profile menu, where profiles are picked, created and deleted.
+    pub mod profile_menu {
+        use super::*;
+        use crate::game::storage;
 
-                        // undo highlight to the current cell 
-                        player.get_cell_mut(r, c).undo();
-                        
-                        if cell_indices.contains(&(r,c)) {
-                            match cell_state_type {
-                                board::CellState::Highlighted => player.get_cell_mut(r, c).highlight(),
-                                board::CellState::InvalidPlacement => player.get_cell_mut(r, c).invalidate(),
-                                _ => {}  // if not one of these two states, do nothing
-                            }
-                        }
+        /// An enum defining what the user chose to do with their profiles.
+        pub enum ProfileAction {
+            Select(String),
+            Create(String),
+            Delete(String),
+            Back
+        }
+
+        /// An enum defining what the menu is waiting on the user for.
+        enum Prompt {
+            Browsing,
+            // the name of the new profile typed so far
+            Naming(String),
+            ConfirmingDelete,
+        }
+
+        /// Display the profiles in the terminal, marking the active one, and let the user pick,
+        /// create or delete one.
+        pub fn show(profiles: &[String], active: Option<&str>) -> std::io::Result<ProfileAction> {
+            // enter an alternate screen for the menu
+            terminal::enable_raw_mode()?;
+            let mut out = render::Renderer::stdout();
+            execute!(out, terminal::EnterAlternateScreen, cursor::Hide, terminal::Clear(terminal::ClearType::All))?;
+
+            // the last option creates a new profile, which is where we start if none is active
+            let mut options: Vec<String> = profiles.iter()
+                .map(|name| if Some(name.as_str()) == active { format!("{} (active)", name) } else { name.clone() })
+                .collect();
+            options.push("New profile".to_string());
+            let mut selected: usize = active.and_then(|active| profiles.iter().position(|name| name == active)).unwrap_or(profiles.len());
+            let keys: input::KeyMap = input::KeyMap::load();
+            let mut prompt: Prompt = Prompt::Browsing;
+            let mut message: Option<String> = None;
+
+            let action: ProfileAction = 'render: loop {
+                let help: String = match &prompt {
+                    Prompt::Browsing => format!(
+                        "Use {} to move, {} to play as the selected profile, {} to delete it, {} to go back",
+                        keys.labels(&[input::Command::MoveUp, input::Command::MoveDown]),
+                        keys.label(input::Command::Confirm),
+                        keys.label(input::Command::DeleteProfile),
+                        keys.label(input::Command::Back)
+                    ),
+                    Prompt::Naming(name) => format!("Name of the new profile: {}_   (Enter to create it, Esc to cancel)", name),
+                    Prompt::ConfirmingDelete => format!(
+                        "Delete {} and everything kept for it? Press {} to confirm, any other key to cancel",
+                        profiles[selected],
+                        keys.label(input::Command::Yes)
+                    ),
+                };
+
+                // clear terminal and print the prompt, along with why the last name was refused
+                queue!(out, cursor::MoveTo(0, 0), terminal::Clear(terminal::ClearType::All))?;
+                queue!(out, style::Print(help))?;
+                if let Some(message) = &message {
+                    queue!(out, cursor::MoveTo(0, 1), style::Print(message))?;
+                }
 
-                        queue!(out, cursor::MoveTo((c as u16) * 3 , (r as u16)  + 4), style::Print(player.get_cell(r, c)))?;
+                // print every profile, highlighting the selected one
+                for (i, option) in options.iter().enumerate() {
+                    if i == selected {
+                        queue!(out, style::SetAttribute(style::Attribute::Reverse))?;
+                    }
+                    queue!(out, cursor::MoveTo(0, 3 + i as u16), style::Print(format!(" {} {}", if i == selected { ">" } else { " " }, option)))?;
+                    if i == selected {
+                        queue!(out, style::SetAttribute(style::Attribute::NoReverse))?;
                     }
                 }
 
@@ -392,166 +586,1787 @@ pub mod game {
                 out.flush()?;
 
                 // poll for the last event that occurred
-                if let event::Event::Key(key) = event::read()? {
-                    if key.kind == event::KeyEventKind::Press {
-                        match key.code {
-                            event::KeyCode::Up => selected.0 = if selected.0 == 0 { board::ROWS - 1 } else { selected.0 - 1 },
-                            event::KeyCode::Down => selected.0 = (selected.0 + 1) % board::ROWS,
-                            event::KeyCode::Left => selected.1 = if selected.1 == 0 { board::COLS - 1 } else { selected.1 - 1 },
-                            event::KeyCode::Right => selected.1 = (selected.1 + 1) % board::COLS,
-                            // allow for caps lock
-                            event::KeyCode::Char('r') | event::KeyCode::Char('R') => ship_orientation = ship_orientation.next(),
-
-                            // if tab, swap through the selected ships
-                            event::KeyCode::Tab => ship_selection = (ship_selection + 1) % ship::ShipType::ALL.len(),
-
-                            // try to confirm the ship selection if valid. otherwise, do nothing
-                            event::KeyCode::Enter => {
-                                // if enter is pressed before all ships are placed, try to place the selected ship
-                                if (!ship_has_been_placed[ship_selection]) && (cell_state_type != board::CellState::InvalidPlacement) {
-                                    player.add_ship(cell_indices, selected_ship_type);
-                                    ship_has_been_placed[ship_selection] = true;
+                if let event::Event::Key(key) = event::read()?
+                    && key.kind == event::KeyEventKind::Press {
+                    message = None;
+                    prompt = match prompt {
+                        Prompt::Naming(mut name) => match key.code {
+                            // only accept characters that keep the name usable, ignoring trailing spaces while typing
+                            event::KeyCode::Char(c) => {
+                                name.push(c);
+                                if name.chars().count() > storage::MAX_PROFILE_NAME || !storage::is_valid_profile_name(name.trim_end()) {
+                                    name.pop();
                                 }
-                                // else, if all ships have been placed, exit the setup loop
-                                else if ship_has_been_placed.iter().all(|x| x == &true) {
-                                    // before continuing, undo the cell highlights
-                                    for r in 0..board::ROWS {
-                                        for c in 0..board::COLS {
-                                            // undo highlight to the current cell 
-                                            player.get_cell_mut(r, c).undo();
-                                        }
-                                    }
-                                    break 'render ShipSetupOption::Continue;
+                                Prompt::Naming(name)
+                            },
+                            event::KeyCode::Backspace => {
+                                name.pop();
+                                Prompt::Naming(name)
+                            },
+                            event::KeyCode::Enter => {
+                                let name: String = name.trim().to_string();
+                                if profiles.iter().any(|other| other.eq_ignore_ascii_case(&name)) {
+                                    message = Some(format!("There already is a profile named {}", name));
+                                    Prompt::Naming(name)
+                                } else if storage::is_valid_profile_name(&name) {
+                                    break 'render ProfileAction::Create(name);
+                                } else {
+                                    Prompt::Naming(name)
                                 }
                             },
-
-                            // break render loop if user hits esc
-                            event::KeyCode::Esc => break 'render ShipSetupOption::Quit,
-                            _ => {}
-                        }
-                    }
-                }          
+                            event::KeyCode::Esc => Prompt::Browsing,
+                            _ => Prompt::Naming(name),
+                        },
+                        Prompt::ConfirmingDelete => match keys.command(&key, &[input::Command::Yes]) {
+                            Some(_) => break 'render ProfileAction::Delete(profiles[selected].clone()),
+                            None => Prompt::Browsing,
+                        },
+                        Prompt::Browsing => match keys.command(&key, &input::PROFILES) {
+                            // pressing up or down cycles back to the other end of the list while navigating.
+                            Some(input::Command::MoveUp) => {
+                                selected = (selected + options.len() - 1) % options.len();
+                                Prompt::Browsing
+                            },
+                            Some(input::Command::MoveDown) => {
+                                selected = (selected + 1) % options.len();
+                                Prompt::Browsing
+                            },
+                            Some(input::Command::Confirm) if selected == profiles.len() => Prompt::Naming(String::new()),
+                            Some(input::Command::Confirm) => break 'render ProfileAction::Select(profiles[selected].clone()),
+                            Some(input::Command::Back) => break 'render ProfileAction::Back,
+                            Some(input::Command::DeleteProfile) if selected < profiles.len() => Prompt::ConfirmingDelete,
+                            _ => Prompt::Browsing,
+                        },
+                    };
+                }
             };
 
-            // leave the main menu screen.
+            // leave the menu screen.
             execute!(out, cursor::Show, terminal::LeaveAlternateScreen)?;
             terminal::disable_raw_mode()?;
 
-            // return an Ok with the selected menu option
-            Ok(selected_ship_setup_option)
+            Ok(action)
         }
     }
 
-    pub mod main_loop {
-
+    /// Module for displaying a message that the user has to acknowledge before carrying on.
+    pub mod notice {
         use super::*;
 
-        // constant for offsetting opponent's board rendering
-        const OPPONENT_BOARD_OFFSET: u16 = 60;
-
-        pub fn show_once(
-            out: &mut std::io::Stdout, 
-            turn_count: usize,
-            player: &mut player::Player, 
-            opponent: &mut player::Player,
-            player_a_cursor_position: &mut(usize, usize)
-        ) -> std::io::Result<Option<(usize, usize)>> {
-            // clear terminal and print the title and movement commands
-            queue!(out, cursor::MoveTo(0, 0), terminal::Clear(terminal::ClearType::All))?;
-            queue!(out, style::Print("Use ←/↑/→/↓ to move, Enter to guess a location on the opponent's board, Esc to quit the game\n\n"))?;
-            queue!(out, style::Print(format!("TURN: {}\n\n", turn_count)))?;
+        /// Display the lines of the message in the terminal until the user presses Enter or Esc.
+        pub fn show(lines: &[String]) -> std::io::Result<()> {
+            let keys: input::KeyMap = input::KeyMap::load();
+            let prompt: String = format!("Press {} to continue", keys.label(input::Command::Confirm));
+            wait(lines, &prompt, |key| keys.command(key, &[input::Command::Confirm, input::Command::Back]).map(|_| ()))
+        }
 
-            // print each cell in the board
-            for r in 0..board::ROWS {
-                for c in 0..board::COLS {
+        /// Display the lines of the message along with a question, until the user answers it with the
+        /// yes key or any other key. Returns true if the answer is yes.
+        pub fn confirm(lines: &[String], question: &str) -> std::io::Result<bool> {
+            let keys: input::KeyMap = input::KeyMap::load();
+            let prompt: String = format!("{} Press {} to confirm, any other key to cancel", question, keys.label(input::Command::Yes));
+            wait(lines, &prompt, |key| Some(keys.command(key, &[input::Command::Yes]).is_some()))
+        }
 
-                    // undo highlight to the current cell 
-                    opponent.get_cell_mut(r, c).undo();
-                    
-                    if &(r, c) == player_a_cursor_position {
-                        opponent.get_cell_mut(r, c).highlight();
-                    }
+        /// Display the lines of the message followed by a prompt until a key press gives an answer.
+        fn wait<T>(lines: &[String], prompt: &str, answer: impl Fn(&event::KeyEvent) -> Option<T>) -> std::io::Result<T> {
+            // enter an alternate screen for the message
+            terminal::enable_raw_mode()?;
+            let mut out = render::Renderer::stdout();
+            execute!(out, terminal::EnterAlternateScreen, cursor::Hide, terminal::Clear(terminal::ClearType::All))?;
 
-                    // print both the player's and opponent's boards
-                    queue!(out, cursor::MoveTo((c as u16) * 3 , (r as u16)  + 4), style::Print(player.get_cell(r, c)))?;
-                    queue!(out, cursor::MoveTo((c as u16) * 3 + OPPONENT_BOARD_OFFSET , (r as u16)  + 4), style::Print(opponent.get_hidden_cell(r, c)))?;
+            // wait for the user to answer, drawing the message again whenever the terminal is resized
+            let answered: T = loop {
+                // long lines are wrapped, keeping the indent they start with
+                let (width, _) = terminal_size();
+                let mut wrapped: Vec<String> = vec![];
+                for line in lines {
+                    let indent: usize = line.len() - line.trim_start().len();
+                    wrapped.extend(wrap(line, width.saturating_sub(indent as u16)).into_iter().map(|part| format!("{}{}", &line[..indent], part)));
                 }
-            }
 
-            // write all output to the screen
-            out.flush()?;
+                queue!(out, cursor::MoveTo(0, 0), terminal::Clear(terminal::ClearType::All))?;
+                for (i, line) in wrapped.iter().enumerate() {
+                    queue!(out, cursor::MoveTo(0, i as u16), style::Print(line))?;
+                }
+                for (i, line) in wrap(prompt, width).iter().enumerate() {
+                    queue!(out, cursor::MoveTo(0, wrapped.len() as u16 + 1 + i as u16), style::Print(line))?;
+                }
+                out.flush()?;
 
-            // poll for the last event that occurred
-            if let event::Event::Key(key) = event::read()? {
-                if key.kind == event::KeyEventKind::Press {
-                    match key.code {
-                        event::KeyCode::Up => player_a_cursor_position.0 = if player_a_cursor_position.0 == 0 { board::ROWS - 1 } else { player_a_cursor_position.0 - 1 },
-                        event::KeyCode::Down => player_a_cursor_position.0 = if player_a_cursor_position.0 == board::ROWS - 1 { 0 } else { player_a_cursor_position.0 + 1 },
-                        event::KeyCode::Left => player_a_cursor_position.1 = if player_a_cursor_position.1 == 0 { board::COLS - 1 } else { player_a_cursor_position.1 - 1 },
-                        event::KeyCode::Right => player_a_cursor_position.1 = if player_a_cursor_position.1 == board::COLS - 1 { 0 } else { player_a_cursor_position.1 + 1 },
-                        event::KeyCode::Enter => {
-                            return Ok(Some(*player_a_cursor_position));
-                        },
-                        event::KeyCode::Esc => {
-                            return Err(std::io::Error::new(std::io::ErrorKind::Other, "User exited game"));
-                        },
-                        _ => {}
-                    }
+                if let event::Event::Key(key) = event::read()?
+                    && key.kind == event::KeyEventKind::Press
+                    && let Some(answered) = answer(&key) {
+                    break answered;
                 }
-            } 
+            };
 
-            Ok(None)
+            // leave the message screen.
+            execute!(out, cursor::Show, terminal::LeaveAlternateScreen)?;
+            terminal::disable_raw_mode()?;
+
+            Ok(answered)
         }
     }
 
-
-    pub mod win_screen {
-
+    /// Module for displaying the settings editor.
+    pub mod settings_menu {
         use super::*;
+        use crate::game::components::{ai, board, ship};
+        use crate::game::{rules, settings};
+
+        /// The cells shown in the theme preview, along with what they stand for.
+        const PREVIEW: [(board::CellState, &str); 6] = [
+            (board::CellState::Empty, "Water"),
+            (board::CellState::Guessed, "Miss"),
+            (board::CellState::OwnShip(ship::ShipType::ALL[0]), "Ship"),
+            (board::CellState::HitShip, "Hit"),
+            (board::CellState::Highlighted, "Aim"),
+            (board::CellState::InvalidPlacement, "Blocked"),
+        ];
+
+        /// An enum defining every setting that can be changed from the editor, in the order they are listed.
+        #[derive(Clone, Copy, PartialEq)]
+        enum Field {
+            Mode,
+            Difficulty,
+            Theme,
+            Charset,
+            Keys,
+            ConfirmClicks,
+            AnimationSpeed,
+            Host,
+            Port,
+        }
 
-        // constant for offsetting opponent's board rendering
-        const OPPONENT_BOARD_OFFSET: u16 = 60;
+        impl Field {
+            /// A static array containing every field to iterate over.
+            const ALL: [Field; 9] = [
+                Field::Mode,
+                Field::Difficulty,
+                Field::Theme,
+                Field::Charset,
+                Field::Keys,
+                Field::ConfirmClicks,
+                Field::AnimationSpeed,
+                Field::Host,
+                Field::Port
+            ];
 
-        pub fn show(
-            player: &player::Player, 
-            opponent: &player::Player,
-            winner: &str
+            /// Get the label the field is listed under.
+            fn label(&self) -> &'static str {
+                match self {
+                    Field::Mode => "Default game mode",
+                    Field::Difficulty => "Default difficulty",
+                    Field::Theme => "Theme",
+                    Field::Charset => "Characters",
+                    Field::Keys => "Keys",
+                    Field::ConfirmClicks => "Click twice to fire",
+                    Field::AnimationSpeed => "Animation speed",
+                    Field::Host => "Network host",
+                    Field::Port => "Network port",
+                }
+            }
+
+            /// Returns true if the field is typed in rather than picked from a list.
+            fn is_text(&self) -> bool {
+                matches!(self, Field::Host | Field::Port)
+            }
+
+            /// Get the current value of the field, as shown in the editor.
+            fn value(&self, settings: &settings::Settings) -> String {
+                match self {
+                    Field::Mode => settings.rules.mode.to_string(),
+                    Field::Difficulty => settings.rules.difficulty.to_string(),
+                    Field::Theme => settings.display.theme.to_string(),
+                    Field::Charset => settings.display.charset.to_string(),
+                    Field::Keys => settings.keys.preset.to_string(),
+                    Field::ConfirmClicks => if settings.mouse.confirm_clicks { "On" } else { "Off" }.to_string(),
+                    Field::AnimationSpeed => settings.display.animation_speed.to_string(),
+                    Field::Host => settings.network.host.clone(),
+                    Field::Port => settings.network.port.to_string(),
+                }
+            }
+
+            /// Move a field that is picked from a list to the next or previous value.
+            fn cycle(&self, settings: &mut settings::Settings, step: isize) {
+                match self {
+                    Field::Mode => settings.rules.mode = cycle(&rules::GameMode::ALL, settings.rules.mode, step),
+                    Field::Difficulty => settings.rules.difficulty = cycle(&ai::Difficulty::ALL, settings.rules.difficulty, step),
+                    Field::Theme => settings.display.theme = cycle(&themes::Theme::ALL, settings.display.theme, step),
+                    // switch straight away, so the preview and help show the characters picked
+                    Field::Charset => {
+                        settings.display.charset = cycle(&settings::Charset::ALL, settings.display.charset, step);
+                        themes::use_charset(settings.display.charset);
+                    },
+                    Field::Keys => settings.keys.preset = cycle(&settings::KeyPreset::ALL, settings.keys.preset, step),
+                    Field::ConfirmClicks => settings.mouse.confirm_clicks = !settings.mouse.confirm_clicks,
+                    Field::AnimationSpeed => settings.display.animation_speed = cycle(&settings::AnimationSpeed::ALL, settings.display.animation_speed, step),
+                    Field::Host | Field::Port => {},
+                }
+            }
+
+            /// Set a field that is typed in. Returns a description of the problem if the text isn't a valid value.
+            fn set(&self, settings: &mut settings::Settings, text: &str) -> Result<(), String> {
+                match self {
+                    Field::Host if settings::is_valid_host(text) => settings.network.host = text.to_string(),
+                    Field::Host => return Err(format!("'{}' is not a host name or address", text)),
+                    Field::Port => match text.parse::<u16>() {
+                        Ok(port) if port > 0 => settings.network.port = port,
+                        _ => return Err(format!("'{}' is not a port between 1 and 65535", text)),
+                    },
+                    _ => {},
+                }
+                Ok(())
+            }
+        }
+
+        /// Get the value after the current one in the list, moving by the given step and wrapping around.
+        fn cycle<T: Copy + PartialEq>(values: &[T], current: T, step: isize) -> T {
+            let index: usize = values.iter().position(|value| *value == current).unwrap_or(0);
+            values[(index as isize + step).rem_euclid(values.len() as isize) as usize]
+        }
+
+        /// Display the settings in the terminal and let the user change them until they go back, asking
+        /// whether to keep the changes if there are any. Returns true if the changes are to be saved,
+        /// while discarded changes leave the settings as they were.
+        pub fn show(settings: &mut settings::Settings) -> std::io::Result<bool> {
+            // enter an alternate screen for the editor
+            terminal::enable_raw_mode()?;
+            let mut out = render::Renderer::stdout();
+            execute!(out, terminal::EnterAlternateScreen, cursor::Hide, terminal::Clear(terminal::ClearType::All))?;
+
+            let original: settings::Settings = settings.clone();
+            let mut selected: usize = 0;
+            let mut changed: bool = false;
+            // the text typed into the selected field so far, while it is being edited
+            let mut editing: Option<String> = None;
+            // whether the user is being asked to save or discard their changes before going back
+            let mut leaving: bool = false;
+            let mut message: Option<String> = None;
+            let save: bool = loop {
+                // the keys follow the preset being edited, so picking a preset tries it out straight away
+                let keys: input::KeyMap = input::KeyMap::new(&settings.keys);
+                let help: String = match (&editing, leaving) {
+                    (Some(_), _) => "Type the new value, Enter to confirm, Esc to cancel".to_string(),
+                    (None, true) => format!(
+                        "Save the changes? Press {} to save them, {} to discard them, any other key to keep editing",
+                        keys.label(input::Command::Yes),
+                        keys.label(input::Command::Back)
+                    ),
+                    (None, false) => format!(
+                        "Use {} to move, {} to change, {} to type a value, {} to go back",
+                        keys.labels(&[input::Command::MoveUp, input::Command::MoveDown]),
+                        keys.labels(&[input::Command::MoveLeft, input::Command::MoveRight]),
+                        keys.label(input::Command::Confirm),
+                        keys.label(input::Command::Back)
+                    ),
+                };
+
+                // clear terminal and print the help, along with why the last value was refused
+                queue!(out, cursor::MoveTo(0, 0), terminal::Clear(terminal::ClearType::All))?;
+                queue!(out, style::Print(help))?;
+                if let Some(message) = &message {
+                    queue!(out, cursor::MoveTo(0, 1), style::Print(message))?;
+                }
+
+                // print every field with its value, highlighting the selected one
+                for (i, field) in Field::ALL.iter().enumerate() {
+                    let value: String = match (&editing, i == selected) {
+                        (Some(text), true) => format!("{}_", text),
+                        _ if field.is_text() => field.value(settings),
+                        _ => format!("< {} >", field.value(settings)),
+                    };
+                    if i == selected {
+                        queue!(out, style::SetAttribute(style::Attribute::Reverse))?;
+                    }
+                    queue!(out, cursor::MoveTo(0, 3 + i as u16), style::Print(format!(" {} {:<20} {}", if i == selected { ">" } else { " " }, field.label(), value)))?;
+                    if i == selected {
+                        queue!(out, style::SetAttribute(style::Attribute::NoReverse))?;
+                    }
+                }
+
+                // preview the theme being picked, so it can be judged before leaving the editor
+                let theme: themes::Theme = settings.display.theme;
+                queue!(out, cursor::MoveTo(0, 4 + Field::ALL.len() as u16), style::Print("Preview:"))?;
+                for (i, (state, meaning)) in PREVIEW.iter().enumerate() {
+                    queue!(out, cursor::MoveTo(10 + i as u16 * 12, 4 + Field::ALL.len() as u16), style::Print(theme.cell(*state)), style::Print(format!(" {}", meaning)))?;
+                }
+
+                // list the keys that replace the preset's, which can only be changed in the settings file
+                let overrides_row: u16 = 6 + Field::ALL.len() as u16;
+                match settings.keys.bindings.is_empty() {
+                    true => queue!(out, cursor::MoveTo(0, overrides_row), style::Print("Key overrides: none, they can be added under [keys.bindings] in the settings file"))?,
+                    false => {
+                        queue!(out, cursor::MoveTo(0, overrides_row), style::Print("Key overrides, from the settings file:"))?;
+                        for (i, (command, names)) in settings.keys.bindings.iter().enumerate() {
+                            queue!(out, cursor::MoveTo(0, overrides_row + 1 + i as u16), style::Print(format!("   {:<20} {}", command.to_string(), names)))?;
+                        }
+                    },
+                }
+
+                // write all output to the screen
+                out.flush()?;
+
+                // poll for the last event that occurred
+                if let event::Event::Key(key) = event::read()?
+                    && key.kind == event::KeyEventKind::Press {
+                    message = None;
+                    let field: Field = Field::ALL[selected];
+                    if leaving {
+                        match keys.command(&key, &[input::Command::Yes, input::Command::Back]) {
+                            Some(input::Command::Yes) => break true,
+                            Some(_) => break false,
+                            None => leaving = false,
+                        }
+                        continue;
+                    }
+                    match editing.as_mut() {
+                        Some(text) => match key.code {
+                            event::KeyCode::Char(c) => text.push(c),
+                            event::KeyCode::Backspace => { text.pop(); },
+                            event::KeyCode::Enter => {
+                                match field.set(settings, text) {
+                                    Ok(()) => changed = true,
+                                    Err(problem) => message = Some(problem),
+                                }
+                                editing = None;
+                            },
+                            event::KeyCode::Esc => editing = None,
+                            _ => {}
+                        },
+                        None => match keys.command(&key, &input::MENU) {
+                            // pressing up or down cycles back to the other end of the list while navigating.
+                            Some(input::Command::MoveUp) => selected = (selected + Field::ALL.len() - 1) % Field::ALL.len(),
+                            Some(input::Command::MoveDown) => selected = (selected + 1) % Field::ALL.len(),
+                            Some(input::Command::MoveLeft) if !field.is_text() => {
+                                field.cycle(settings, -1);
+                                changed = true;
+                            },
+                            Some(input::Command::MoveRight | input::Command::Confirm) if !field.is_text() => {
+                                field.cycle(settings, 1);
+                                changed = true;
+                            },
+                            Some(input::Command::Confirm) => editing = Some(field.value(settings)),
+                            Some(input::Command::Back) if changed => leaving = true,
+                            Some(input::Command::Back) => break false,
+                            _ => {}
+                        },
+                    }
+                }
+            };
+
+            // put the settings back as they were, including the characters switched to while editing
+            if !save {
+                *settings = original;
+                themes::use_charset(settings.display.charset);
+            }
+
+            // leave the settings screen.
+            execute!(out, cursor::Show, terminal::LeaveAlternateScreen)?;
+            terminal::disable_raw_mode()?;
+
+            Ok(save)
+        }
+    }
+
+}
+
+pub mod game {
+    use super::*;
+    use crate::game::achievements;
+    use crate::game::components::{board, player, ship};
+
+    /// Where the box announcing newly unlocked achievements is drawn.
+    const TOAST_ORIGIN: (u16, u16) = (8, 8);
+
+    /// The number of columns left of a board taken up by the row labels.
+    const LABEL_WIDTH: u16 = 2;
+
+    pub mod board_setup {
+        use std::vec;
+
+        use super::*;
+        use crate::game::{formations, storage};
+
+        /// An Enum defining the possible states that can be returned from the render loop.
+        pub enum ShipSetupOption {
+            Continue,
+            Quit
+        }
+
+        /// An enum defining what the keys are currently used for.
+        enum Prompt {
+            Placing,
+            // typing the name to save the formation under
+            Naming(String),
+            // choosing which saved formation to load
+            Choosing(usize),
+        }
+
+        /// Get the cells the selected ship would cover from the selected cell, along with the state they
+        /// should be rendered in. The placement is invalid if it leaves the board or overlaps another ship.
+        fn get_ship_placement_cell_states(
+            player: &player::Player,
+            ship_type: &ship::ShipType, 
+            orientation: &ship::ShipOrientation,
+            mirrored: bool,
+            selected_cell: &(usize, usize),
+        ) -> (vec::Vec<(usize, usize)>, board::CellState) {
+            // initialize vector for indices and cell state that will be rendered
+            let mut indices: vec::Vec<(usize, usize)> = vec![];
+            let mut state: board::CellState = board::CellState::Highlighted;
+
+            // lay the ship's shape out from the selected cell, leaving out the parts that
+            // fall off the edge of the board
+            for (r, c) in ship_type.footprint(*selected_cell, *orientation, mirrored) {
+                if !board::Board::in_bounds(r, c) {
+                    state = board::CellState::InvalidPlacement;
+                    continue;
+                }
+                indices.push((r as usize, c as usize));
+            };
+
+            if player.try_place_ship(selected_cell.0, selected_cell.1, *orientation, mirrored, *ship_type).is_none() {
+                state = board::CellState::InvalidPlacement;
+            }
+            (indices, state)
+        }
+        
+        /// Display the board setup in the terminal and let the player place every ship in the fleet.
+        /// Players with a profile can also save their placement as a formation and load it again later.
+        pub fn show(player: &mut player::Player, fleet: &[ship::ShipType], profile: Option<&str>) -> std::io::Result<ShipSetupOption> {
+            // enter an alternate screen
+            terminal::enable_raw_mode()?;
+            let mut out = render::Renderer::stdout();
+            execute!(out, terminal::EnterAlternateScreen, event::EnableMouseCapture, cursor::Hide, terminal::Clear(terminal::ClearType::All))?;
+
+            // set the necessary values for tracking the ship placement state
+            let mut selected: (usize, usize) = (0, 0);
+            let mut ship_selection: usize = 0;
+            let mut ship_has_been_placed: vec::Vec<bool> = vec![false; fleet.len()];
+            let mut selected_ship_type: ship::ShipType;
+            let mut cell_indices: vec::Vec<(usize, usize)>;
+            let mut ship_orientation: ship::ShipOrientation = ship::ShipOrientation::Left;
+            let mut ship_mirrored: bool = false;
+            let mut prompt: Prompt = Prompt::Placing;
+            let mut message: Option<String> = None;
+
+            // the formations saved by the profile. they are left out if they can't be read
+            let mut formations: vec::Vec<formations::Formation> = match profile.map(storage::load_formations) {
+                Some(Ok(formations)) => formations,
+                Some(Err(err)) => {
+                    message = Some(format!("Your saved formations couldn't be read: {}", err));
+                    vec![]
+                },
+                None => vec![],
+            };
+
+            // flipping is only worth mentioning if the fleet has ships whose mirror image is different
+            let keys: input::KeyMap = input::KeyMap::load();
+            let theme: themes::Theme = themes::Theme::load();
+            let flip: String = match fleet.iter().any(|ship_type| ship_type.variants().len() > 4) {
+                true => format!("{} to flip it, ", keys.label(input::Command::Flip)),
+                false => String::new(),
+            };
+            let help: String = format!(
+                "Use {} to move, {} to rotate the ship's orientation, {}{} to quit the game",
+                keys.labels(&input::DIRECTIONS),
+                keys.label(input::Command::Rotate),
+                flip,
+                keys.label(input::Command::Back)
+            );
+            let mut cell_state_type: board::CellState;
+
+            // begin rendering loop. at the end of this loop, we get returned an option that
+            // the user has completed setting up and that the game is ready to progress to
+            // the next stage
+            let selected_ship_setup_option: ShipSetupOption = 'render: loop {
+                let auto_place: String = format!("{} to place the remaining ships at random", keys.label(input::Command::AutoPlace));
+                let formation_help: String = match &prompt {
+                    Prompt::Placing if profile.is_some() => format!(
+                        "{}, {} to save the formation, {} to load a saved one",
+                        auto_place,
+                        keys.label(input::Command::SaveFormation),
+                        keys.label(input::Command::LoadFormation)
+                    ),
+                    Prompt::Placing => auto_place,
+                    Prompt::Naming(name) => format!("Name of the formation: {}_   (Enter to save it, Esc to cancel)", name),
+                    Prompt::Choosing(i) => format!(
+                        "Formation to load: < {} >   ({} to choose, {} to load it, {} to cancel)",
+                        formations[*i].get_name(),
+                        keys.labels(&[input::Command::MoveLeft, input::Command::MoveRight]),
+                        keys.label(input::Command::Confirm),
+                        keys.label(input::Command::Back)
+                    ),
+                };
+
+                // lay the screen out for the size of the terminal. the help and the ships wrap onto as
+                // many lines as they need, followed by the board and the last message
+                let (width, _) = terminal_size();
+                let help_lines: Vec<String> = [wrap(&help, width), wrap(&formation_help, width)].concat();
+                let mut ship_labels: vec::Vec<String> = fleet.iter().map(|ship| ship.to_string()).collect();
+                if ship_has_been_placed.iter().all(|x| x == &true) {
+                    ship_labels.push(format!("Press {} to Continue", keys.label(input::Command::Confirm)));
+                }
+                let ship_positions: vec::Vec<(u16, u16)> = flow(&ship_labels, 15, width);
+                let ships_row: u16 = help_lines.len() as u16;
+                let origin: (u16, u16) = (LABEL_WIDTH, ships_row + ship_positions.last().map_or(0, |position| position.1) + 2);
+                let needed: (u16, u16) = (LABEL_WIDTH + board_width(false), origin.1 + board::ROWS as u16 + 2);
+                if too_small(needed) {
+                    if wait_for_room(&mut out, needed, &keys)? {
+                        break 'render ShipSetupOption::Quit;
+                    }
+                    continue;
+                }
+
+                // clear terminal and print the title and movement commands
+                queue!(out, cursor::MoveTo(0, 0), terminal::Clear(terminal::ClearType::All))?;
+                for (i, line) in help_lines.iter().enumerate() {
+                    queue!(out, cursor::MoveTo(0, i as u16), style::Print(line))?;
+                }
+                if let Some(message) = &message {
+                    queue!(out, cursor::MoveTo(0, origin.1 + board::ROWS as u16 + 1), style::Print(message))?;
+                }
+
+                for (i, (label, (x, y))) in ship_labels.iter().zip(ship_positions).enumerate() {
+                    // highlight the currently selected ship
+                    if i == ship_selection {
+                        queue!(out, style::SetAttribute(style::Attribute::Reverse))?;
+                    }
+                    // if the ship has been placed, gray it out
+                    if ship_has_been_placed.get(i) == Some(&true) {
+                        queue!(out, style::SetForegroundColor(style::Color::DarkGrey))?;
+                    }
+
+                    queue!(out, cursor::MoveTo(x, ships_row + y), style::Print(label))?;
+
+                    // reset styles after printing
+                    if ship_has_been_placed.get(i) == Some(&true) {
+                        queue!(out, style::SetForegroundColor(style::Color::Reset))?;
+                    }
+                    if i == ship_selection {
+                        queue!(out, style::SetAttribute(style::Attribute::NoReverse))?;
+                    }
+                }
+
+                // find the ship that corresponds to the currently selected index
+                selected_ship_type = fleet[ship_selection];
+                (cell_indices, cell_state_type) = get_ship_placement_cell_states(player, &selected_ship_type, &ship_orientation, ship_mirrored, &selected);
+
+                // print each cell in the board
+                for r in 0..board::ROWS {
+                    for c in 0..board::COLS {
+
+                        // undo highlight to the current cell 
+                        player.get_cell_mut(r, c).undo();
+                        
+                        if cell_indices.contains(&(r,c)) {
+                            match cell_state_type {
+                                board::CellState::Highlighted => player.get_cell_mut(r, c).highlight(),
+                                board::CellState::InvalidPlacement => player.get_cell_mut(r, c).invalidate(),
+                                _ => {}  // if not one of these two states, do nothing
+                            }
+                        }
+                    }
+                }
+                draw_board(&mut out, origin, false, theme, |r, c| player.get_cell(r, c).clone())?;
+
+                // write all output to the screen
+                out.flush()?;
+
+                // poll for the last event that occurred. while placing ships, clicking a cell of the board
+                // places the selected ship there, and right clicking or scrolling rotates it
+                let (code, command): (Option<event::KeyCode>, Option<input::Command>) = match event::read()? {
+                    event::Event::Key(key) if key.kind == event::KeyEventKind::Press => {
+                        let commands: &[input::Command] = if matches!(prompt, Prompt::Placing) { &input::PLACING } else { &input::MENU };
+                        (Some(key.code), keys.command(&key, commands))
+                    },
+                    event::Event::Mouse(mouse) if matches!(prompt, Prompt::Placing) => (None, match mouse.kind {
+                        event::MouseEventKind::Down(event::MouseButton::Left) => {
+                            match cell_at(origin, false, (mouse.column, mouse.row)) {
+                                Some(cell) => {
+                                    selected = cell;
+                                    (cell_indices, cell_state_type) = get_ship_placement_cell_states(player, &selected_ship_type, &ship_orientation, ship_mirrored, &selected);
+                                    // once the ship is placed, a click only moves the selection
+                                    (!ship_has_been_placed[ship_selection]).then_some(input::Command::Confirm)
+                                },
+                                None => None,
+                            }
+                        },
+                        event::MouseEventKind::Down(event::MouseButton::Right)
+                        | event::MouseEventKind::ScrollUp
+                        | event::MouseEventKind::ScrollDown => Some(input::Command::Rotate),
+                        _ => None,
+                    }),
+                    _ => (None, None),
+                };
+                if code.is_some() || command.is_some() {
+                    message = None;
+                    prompt = match prompt {
+                        Prompt::Naming(mut name) => match code {
+                            Some(event::KeyCode::Char(c)) => {
+                                if !c.is_control() && name.chars().count() < formations::MAX_FORMATION_NAME {
+                                    name.push(c);
+                                }
+                                Prompt::Naming(name)
+                            },
+                            Some(event::KeyCode::Backspace) => {
+                                name.pop();
+                                Prompt::Naming(name)
+                            },
+                            Some(event::KeyCode::Enter) if !name.trim().is_empty() => {
+                                // saving under a name that's already taken replaces the old formation
+                                let name: &str = name.trim();
+                                formations.retain(|formation| !formation.get_name().eq_ignore_ascii_case(name));
+                                formations.push(formations::Formation::from_player(name, player));
+                                message = Some(match profile.map(|profile| storage::save_formations(profile, &formations)) {
+                                    Some(Err(err)) => format!("The formation couldn't be saved: {}", err),
+                                    _ => format!("Saved the formation as {}", name),
+                                });
+                                Prompt::Placing
+                            },
+                            Some(event::KeyCode::Esc) => Prompt::Placing,
+                            _ => Prompt::Naming(name),
+                        },
+                        Prompt::Choosing(i) => match command {
+                            Some(input::Command::MoveLeft) => Prompt::Choosing((i + formations.len() - 1) % formations.len()),
+                            Some(input::Command::MoveRight) => Prompt::Choosing((i + 1) % formations.len()),
+                            Some(input::Command::Confirm) => {
+                                // loading replaces every ship placed so far
+                                match formations[i].apply(player, fleet) {
+                                    Ok(()) => {
+                                        ship_has_been_placed = vec![true; fleet.len()];
+                                        message = Some(format!("Loaded the formation {}", formations[i].get_name()));
+                                    },
+                                    Err(err) => message = Some(format!("The formation {} can't be used: {}", formations[i].get_name(), err)),
+                                }
+                                Prompt::Placing
+                            },
+                            Some(input::Command::Back) => Prompt::Placing,
+                            _ => Prompt::Choosing(i),
+                        },
+                        Prompt::Placing => match command {
+                            Some(input::Command::MoveUp) => {
+                                selected.0 = if selected.0 == 0 { board::ROWS - 1 } else { selected.0 - 1 };
+                                Prompt::Placing
+                            },
+                            Some(input::Command::MoveDown) => {
+                                selected.0 = (selected.0 + 1) % board::ROWS;
+                                Prompt::Placing
+                            },
+                            Some(input::Command::MoveLeft) => {
+                                selected.1 = if selected.1 == 0 { board::COLS - 1 } else { selected.1 - 1 };
+                                Prompt::Placing
+                            },
+                            Some(input::Command::MoveRight) => {
+                                selected.1 = (selected.1 + 1) % board::COLS;
+                                Prompt::Placing
+                            },
+                            Some(input::Command::Rotate) => {
+                                ship_orientation = ship_orientation.next();
+                                Prompt::Placing
+                            },
+                            // flip the ship's shape. this only makes a difference for ships that aren't symmetric
+                            Some(input::Command::Flip) => {
+                                ship_mirrored = !ship_mirrored;
+                                Prompt::Placing
+                            },
+
+                            // place the ships that haven't been placed yet around the ones that have
+                            Some(input::Command::AutoPlace) => {
+                                let remaining: vec::Vec<ship::ShipType> = fleet.iter()
+                                    .zip(ship_has_been_placed.iter())
+                                    .filter(|(_, placed)| !**placed)
+                                    .map(|(ship_type, _)| *ship_type)
+                                    .collect();
+                                match player.auto_place_remaining(&remaining, 100, 10) {
+                                    Ok(()) => ship_has_been_placed = vec![true; fleet.len()],
+                                    Err(_) => message = Some("The remaining ships don't fit around the ones already placed".to_string()),
+                                }
+                                Prompt::Placing
+                            },
+                            Some(input::Command::SaveFormation) if profile.is_some() => {
+                                if ship_has_been_placed.iter().all(|x| x == &true) {
+                                    Prompt::Naming(String::new())
+                                } else {
+                                    message = Some("Place every ship before saving the formation".to_string());
+                                    Prompt::Placing
+                                }
+                            },
+                            Some(input::Command::LoadFormation) if profile.is_some() => {
+                                if formations.is_empty() {
+                                    message = Some("You haven't saved any formations yet".to_string());
+                                    Prompt::Placing
+                                } else {
+                                    Prompt::Choosing(0)
+                                }
+                            },
+
+                            // swap through the selected ships
+                            Some(input::Command::CycleShip) => {
+                                ship_selection = (ship_selection + 1) % fleet.len();
+                                Prompt::Placing
+                            },
+
+                            // try to confirm the ship selection if valid. otherwise, do nothing
+                            Some(input::Command::Confirm) => {
+                                // if enter is pressed before all ships are placed, try to place the selected ship
+                                if (!ship_has_been_placed[ship_selection]) && (cell_state_type != board::CellState::InvalidPlacement) {
+                                    player.add_ship(cell_indices, selected_ship_type, ship_orientation, ship_mirrored);
+                                    ship_has_been_placed[ship_selection] = true;
+                                }
+                                // else, if all ships have been placed, exit the setup loop
+                                else if ship_has_been_placed.iter().all(|x| x == &true) {
+                                    // before continuing, undo the cell highlights
+                                    for r in 0..board::ROWS {
+                                        for c in 0..board::COLS {
+                                            // undo highlight to the current cell 
+                                            player.get_cell_mut(r, c).undo();
+                                        }
+                                    }
+                                    break 'render ShipSetupOption::Continue;
+                                }
+                                Prompt::Placing
+                            },
+
+                            // break render loop if user hits esc
+                            Some(input::Command::Back) => break 'render ShipSetupOption::Quit,
+                            _ => Prompt::Placing,
+                        },
+                    };
+                }
+            };
+
+            // leave the main menu screen.
+            execute!(out, cursor::Show, event::DisableMouseCapture, terminal::LeaveAlternateScreen)?;
+            terminal::disable_raw_mode()?;
+
+            // return an Ok with the selected menu option
+            Ok(selected_ship_setup_option)
+        }
+    }
+
+    /// Module for the screen shown between the turns of human players sharing a computer, so
+    /// that nobody sees the ships of the player who takes the next turn.
+    pub mod pass_screen {
+        use super::*;
+
+        /// Blank the current screen and wait for the next player to press Enter. Returns false if
+        /// Esc is pressed instead, which does what the given description of quitting says.
+        pub fn show_in(out: &mut render::Renderer, name: &str, quitting: &str) -> std::io::Result<bool> {
+            let keys: input::KeyMap = input::KeyMap::load();
+
+            // draw the screen again whenever the terminal is resized
+            loop {
+                queue!(out, cursor::MoveTo(0, 0), terminal::Clear(terminal::ClearType::All))?;
+                queue!(out, style::Print(format!("Pass to {}, then press {}", name, keys.label(input::Command::Confirm))))?;
+                queue!(out, cursor::MoveTo(0, 2), style::Print(format!("Press {} to {}", keys.label(input::Command::Back), quitting)))?;
+                out.flush()?;
+
+                if let event::Event::Key(key) = event::read()?
+                    && key.kind == event::KeyEventKind::Press {
+                    match keys.command(&key, &[input::Command::Confirm, input::Command::Back]) {
+                        Some(input::Command::Confirm) => return Ok(true),
+                        Some(input::Command::Back) => return Ok(false),
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        /// Show the pass screen on an alternate screen of its own, for use outside of the main loop,
+        /// while the players are still setting up and there is no game to save yet.
+        pub fn show(name: &str) -> std::io::Result<bool> {
+            terminal::enable_raw_mode()?;
+            let mut out = render::Renderer::stdout();
+            execute!(out, terminal::EnterAlternateScreen, cursor::Hide, terminal::Clear(terminal::ClearType::All))?;
+
+            let result: std::io::Result<bool> = show_in(&mut out, name, "quit the game");
+
+            // leave the pass screen.
+            execute!(out, cursor::Show, terminal::LeaveAlternateScreen)?;
+            terminal::disable_raw_mode()?;
+
+            result
+        }
+    }
+
+    /// Module for choosing who plays in each seat of a free-for-all or team game.
+    pub mod seat_setup {
+        use super::*;
+        use crate::game::components::ai;
+        use crate::game::game::Controller;
+
+        /// The most players that can sit at the table.
+        pub const MAX_SEATS: usize = 4;
+        /// The fewest players needed for a free-for-all game.
+        pub const MIN_SEATS: usize = 3;
+
+        /// Get every choice for a seat, in the order they are cycled through. None leaves the seat empty,
+        /// which isn't allowed in team play since both teams need two players.
+        fn choices(teams: bool) -> Vec<Option<Controller>> {
+            let mut choices: Vec<Option<Controller>> = vec![Some(Controller::Human)];
+            choices.extend(ai::Difficulty::iter().map(|difficulty| Some(Controller::Computer(difficulty))));
+            if !teams {
+                choices.push(None);
+            }
+            choices
+        }
+
+        /// Describe who sits in a seat.
+        fn describe(choice: &Option<Controller>) -> String {
+            match choice {
+                Some(Controller::Human) => "Human".to_string(),
+                Some(Controller::Computer(difficulty)) => format!("Computer ({})", difficulty),
+                None => "Empty".to_string(),
+            }
+        }
+
+        /// Display the seat setup in the terminal. Returns who plays in each of the occupied seats,
+        /// or None if the user goes back. In team play, seats alternate between the two teams.
+        pub fn show(teams: bool) -> std::io::Result<Option<Vec<Controller>>> {
+            // enter an alternate screen
+            terminal::enable_raw_mode()?;
+            let mut out = render::Renderer::stdout();
+            execute!(out, terminal::EnterAlternateScreen, cursor::Hide, terminal::Clear(terminal::ClearType::All))?;
+            let keys: input::KeyMap = input::KeyMap::load();
+
+            // start with one human against two computers, and the last seat left empty. in
+            // team play, the last seat goes to the human's computer teammate instead
+            let choices: Vec<Option<Controller>> = choices(teams);
+            let mut seats: [usize; MAX_SEATS] = [0, 2, 2, if teams { 2 } else { choices.len() - 1 }];
+            let mut selected: usize = 0;
+
+            let selection: Option<Vec<Controller>> = 'render: loop {
+                let controllers: Vec<Controller> = seats.iter().filter_map(|i| choices[*i]).collect();
+                let ready: bool = controllers.len() >= MIN_SEATS && controllers.contains(&Controller::Human);
+                let required: &str = if teams {
+                    "Team play needs at least one human player"
+                } else {
+                    "A free-for-all needs at least 3 players, one of them human"
+                };
+
+                // clear terminal and print the title and movement commands
+                queue!(out, cursor::MoveTo(0, 0), terminal::Clear(terminal::ClearType::All))?;
+                queue!(out, style::Print(format!(
+                    "Use {} to pick a seat, {} to change who plays in it, {} to continue, {} to go back\n\n",
+                    keys.labels(&[input::Command::MoveUp, input::Command::MoveDown]),
+                    keys.labels(&[input::Command::MoveLeft, input::Command::MoveRight]),
+                    keys.label(input::Command::Confirm),
+                    keys.label(input::Command::Back)
+                )))?;
+
+                for (seat, choice) in seats.iter().enumerate() {
+                    if seat == selected {
+                        queue!(out, style::SetAttribute(style::Attribute::Reverse))?;
+                    }
+                    let label: String = match teams {
+                        true => format!("Seat {} (Team {}): {}", seat + 1, seat % 2 + 1, describe(&choices[*choice])),
+                        false => format!("Seat {}: {}", seat + 1, describe(&choices[*choice])),
+                    };
+                    queue!(out, cursor::MoveTo(0, 2 + seat as u16), style::Print(label))?;
+                    queue!(out, style::SetAttribute(style::Attribute::NoReverse))?;
+                }
+
+                // explain why the game can't start yet
+                let status: String = if ready { format!("Press {} to Continue", keys.label(input::Command::Confirm)) } else { required.to_string() };
+                queue!(out, cursor::MoveTo(0, 3 + MAX_SEATS as u16), style::Print(status))?;
+
+                // write all output to the screen
+                out.flush()?;
+
+                // poll for the last event that occurred
+                if let event::Event::Key(key) = event::read()?
+                    && key.kind == event::KeyEventKind::Press {
+                    match keys.command(&key, &input::MENU) {
+                        Some(input::Command::MoveUp) => selected = if selected == 0 { MAX_SEATS - 1 } else { selected - 1 },
+                        Some(input::Command::MoveDown) => selected = (selected + 1) % MAX_SEATS,
+                        Some(input::Command::MoveLeft) => seats[selected] = (seats[selected] + choices.len() - 1) % choices.len(),
+                        Some(input::Command::MoveRight) => seats[selected] = (seats[selected] + 1) % choices.len(),
+                        Some(input::Command::Confirm) if ready => break 'render Some(controllers),
+                        Some(input::Command::Back) => break 'render None,
+                        _ => {}
+                    }
+                }
+            };
+
+            // leave the seat setup screen.
+            execute!(out, cursor::Show, terminal::LeaveAlternateScreen)?;
+            terminal::disable_raw_mode()?;
+
+            Ok(selection)
+        }
+    }
+
+    /// Draw a board with its top left corner at the given position. Cells of regular boards are three
+    /// columns apart, while small boards put them two columns apart to fit more boards on the screen.
+    /// The columns are numbered on the line above the board, and the rows lettered left of it.
+    fn draw_board(
+        out: &mut render::Renderer,
+        origin: (u16, u16),
+        small: bool,
+        theme: themes::Theme,
+        cell: impl Fn(usize, usize) -> board::Cell
+    ) -> std::io::Result<()> {
+        let spacing: u16 = cell_spacing(small);
+        queue!(out, style::SetForegroundColor(theme.label_colour()))?;
+        for c in 0..board::COLS {
+            queue!(out, cursor::MoveTo(origin.0 + (c as u16) * spacing, origin.1 - 1), style::Print(board::Board::col_label(c)))?;
+        }
+        for r in 0..board::ROWS {
+            queue!(out, cursor::MoveTo(origin.0 - LABEL_WIDTH, origin.1 + r as u16), style::Print(board::Board::row_label(r)))?;
+        }
+        queue!(out, style::SetForegroundColor(style::Color::Reset))?;
+
+        for r in 0..board::ROWS {
+            for c in 0..board::COLS {
+                queue!(out, cursor::MoveTo(origin.0 + (c as u16) * spacing, origin.1 + r as u16), style::Print(theme.cell(cell(r, c).get_state())))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the number of columns between the cells of a board.
+    fn cell_spacing(small: bool) -> u16 {
+        if small { 2 } else { 3 }
+    }
+
+    /// Get the number of columns a board takes up, from its first cell to the end of its last column number.
+    fn board_width(small: bool) -> u16 {
+        cell_spacing(small) * (board::COLS as u16 - 1) + board::Board::col_label(board::COLS - 1).len() as u16
+    }
+
+    /// Find the cell of a board drawn at the given origin that lies under a position on screen. Every
+    /// cell also takes up the gap up to the next cell, so clicking between two cells picks the left one.
+    fn cell_at(origin: (u16, u16), small: bool, position: (u16, u16)) -> Option<(usize, usize)> {
+        let (column, row) = position;
+        if column < origin.0 || row < origin.1 {
+            return None;
+        }
+        let cell: (usize, usize) = ((row - origin.1) as usize, ((column - origin.0) / cell_spacing(small)) as usize);
+        (cell.0 < board::ROWS && cell.1 < board::COLS).then_some(cell)
+    }
+
+    /// Draw a box announcing newly unlocked achievements over whatever is on screen.
+    fn draw_toast(out: &mut render::Renderer, unlocked: &[achievements::Achievement]) -> std::io::Result<()> {
+        let mut lines: Vec<String> = vec![];
+        for achievement in unlocked {
+            lines.push(format!("Achievement unlocked: {}", achievement));
+            lines.push(format!("  {}", achievement.description()));
+        }
+        let width: usize = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+
+        // pad every line to the same width so the box covers the boards behind it
+        queue!(out, style::SetAttribute(style::Attribute::Reverse))?;
+        for (i, line) in lines.iter().enumerate() {
+            queue!(out, cursor::MoveTo(TOAST_ORIGIN.0, TOAST_ORIGIN.1 + i as u16), style::Print(format!(" {:<width$} ", line, width = width)))?;
+        }
+        queue!(out, style::SetAttribute(style::Attribute::NoReverse))?;
+        Ok(())
+    }
+
+    /// The number of columns kept free right of a board for its sidebar during play.
+    const SIDEBAR_WIDTH: u16 = 22;
+
+    /// The fewest lines of the battle log shown below the boards.
+    const LOG_MIN_ROWS: u16 = 3;
+
+    /// Get where each player's board goes on screen, and whether it is drawn small, for boards starting
+    /// on the given row of a terminal of the given width. The board of the player in the given seat always
+    /// comes first on the left, followed by the boards of their allies. With two players the boards are
+    /// drawn at full size side by side, or one above the other if the terminal is too narrow. With more
+    /// players the other boards are drawn small with a name above their labels, filling the space right of
+    /// the player's board before carrying on below it. The boards of an opposing team are drawn side by side.
+    /// The given number of columns is kept free right of every board for its sidebar.
+    fn board_origins(players: usize, seat: usize, allies: &[usize], top: u16, width: u16, sidebar: u16) -> Vec<(usize, (u16, u16), bool)> {
+        // constant for offsetting opponent's board rendering, when there's room for it
+        const OPPONENT_BOARD_OFFSET: u16 = 60;
+        // the columns between a board and the row labels of the board right of it
+        const GAP: u16 = 3;
+        // the rows between the bottom of a board and the top of the board below it, leaving room for the name and column numbers
+        const ROW_GAP: u16 = 3;
+
+        let mut origins: Vec<(usize, (u16, u16), bool)> = vec![(seat, (LABEL_WIDTH, top), false)];
+        let others = allies.iter().copied()
+            .chain((0..players).filter(|other| *other != seat && !allies.contains(other)));
+        let beside: u16 = LABEL_WIDTH + board_width(false) + sidebar + GAP + LABEL_WIDTH;
+        if players == 2 {
+            let offset: u16 = OPPONENT_BOARD_OFFSET.max(beside).min(width.saturating_sub(board_width(false) + sidebar));
+            let origin: (u16, u16) = match offset >= beside {
+                true => (offset, top),
+                false => (LABEL_WIDTH, top + board::ROWS as u16 + ROW_GAP),
+            };
+            origins.extend(others.map(|other| (other, origin, false)));
+        } else {
+            let spacing: u16 = if allies.is_empty() { 24 } else { 22 };
+            let (mut x, mut y) = (beside, top + 1);
+            for other in others {
+                if x + board_width(true) + sidebar > width && x > LABEL_WIDTH {
+                    (x, y) = (LABEL_WIDTH, y + board::ROWS as u16 + ROW_GAP);
+                }
+                origins.push((other, (x, y), true));
+                x += spacing + sidebar;
+            }
+        }
+        origins
+    }
+
+    /// Get the row below the lowest of the boards at the given origins.
+    fn boards_bottom(origins: &[(usize, (u16, u16), bool)]) -> u16 {
+        origins.iter().map(|(_, origin, _)| origin.1 + board::ROWS as u16).max().unwrap_or(0)
+    }
+
+    pub mod main_loop {
+
+        use super::*;
+        use crate::game::components::arsenal;
+        use crate::game::game::Action;
+        use crate::game::stats;
+
+        /// The human player's aim, carried between frames of the main loop.
+        pub struct Targeting {
+            pub cursor: (usize, usize),
+            pub weapon: arsenal::Weapon,
+            pub heading: ship::ShipOrientation,
+            // the seat of the opponent being aimed at
+            pub target: usize,
+            // the seats of the player's teammates, who can't be aimed at
+            pub allies: Vec<usize>,
+            // whether the rules allow ships to be moved instead of firing
+            pub mobile_fleet: bool,
+            // the index of the ship being commanded, if the player is moving a ship instead of firing
+            pub moving: Option<usize>,
+            pub pending_move: Option<ship::ShipMove>,
+            // the coordinate being typed in, such as "B7"
+            pub typed: String,
+            // whether a click only aims, leaving a second click on the same cell to fire
+            pub confirm_clicks: bool,
+            pub keys: input::KeyMap,
+            pub theme: themes::Theme,
+            // the shots waiting to be played to the player, starting with the one being played
+            pub animations: Vec<animation::Animation>,
+            // how many lines the battle log is scrolled back from its latest entry
+            pub log_scroll: usize,
+        }
+
+        impl Default for Targeting {
+            fn default() -> Self {
+                Self {
+                    cursor: (0, 0),
+                    weapon: arsenal::Weapon::Cannon,
+                    heading: ship::ShipOrientation::Right,
+                    target: 1,
+                    allies: vec![],
+                    mobile_fleet: false,
+                    moving: None,
+                    pending_move: None,
+                    typed: String::new(),
+                    confirm_clicks: false,
+                    keys: input::KeyMap::default(),
+                    theme: themes::Theme::Classic,
+                    animations: vec![],
+                    log_scroll: 0
+                }
+            }
+        }
+
+        impl Targeting {
+            /// Switch to the next weapon that still has uses left in the arsenal.
+            fn next_weapon(&mut self, arsenal: &arsenal::Arsenal) {
+                let current: usize = arsenal::Weapon::iter().position(|w| w == self.weapon).unwrap_or(0);
+                for i in 1..=arsenal::Weapon::ALL.len() {
+                    let weapon: arsenal::Weapon = arsenal::Weapon::ALL[(current + i) % arsenal::Weapon::ALL.len()];
+                    if arsenal.can_fire(weapon) {
+                        self.weapon = weapon;
+                        return;
+                    }
+                }
+            }
+
+            /// Select the next ship of the player that is still allowed to move.
+            fn next_ship(&mut self, player: &player::Player) {
+                let movable: Vec<usize> = player.movable_ships();
+                self.pending_move = None;
+                self.moving = match self.moving {
+                    Some(current) => movable.iter().find(|i| **i > current).or(movable.first()).copied(),
+                    None => movable.first().copied(),
+                };
+            }
+
+            /// Add a typed character to the coordinate being typed in, moving the cursor as soon as it
+            /// names a cell. Returns false if the character can't be part of the coordinate.
+            fn type_char(&mut self, c: char) -> bool {
+                let row_letter: bool = self.typed.is_empty()
+                    && c.is_ascii_alphabetic()
+                    && ((c.to_ascii_uppercase() as u8 - b'A') as usize) < board::ROWS;
+                if !row_letter && (self.typed.is_empty() || !c.is_ascii_digit()) {
+                    return false;
+                }
+                self.typed.push(c.to_ascii_uppercase());
+                if let Some(cell) = board::Board::parse_label(&self.typed) {
+                    self.cursor = cell;
+                }
+                true
+            }
+
+            /// Aim at the next opponent, after the current one, that still has ships afloat.
+            fn next_target(&mut self, players: &[player::Player], seat: usize) {
+                for i in 1..=players.len() {
+                    let other: usize = (self.target + i) % players.len();
+                    if other != seat && !self.allies.contains(&other) && !players[other].all_ships_sunk() {
+                        self.target = other;
+                        return;
+                    }
+                }
+            }
+        }
+
+        /// Draw the sidebar of a board, starting at the given position. The player's own fleet and the
+        /// fleets of their allies show how often every ship has been hit, while the fleets of opponents
+        /// only show which ships are sunk, leaving out decoys so they aren't given away. The shots the
+        /// board's owner fired so far and how many of them hit are listed below. Small boards already
+        /// have their owner's name above them, so only full size boards get it at the top of the sidebar.
+        fn draw_sidebar(
+            out: &mut render::Renderer,
+            position: (u16, u16),
+            player: &player::Player,
+            tally: &stats::Tally,
+            own: bool,
+            named: bool
+        ) -> std::io::Result<()> {
+            if named {
+                queue!(out, cursor::MoveTo(position.0, position.1), style::Print(player.get_name()))?;
+            }
+            let mut row: u16 = position.1 + 1;
+            for ship in player.get_ships().iter().filter(|ship| own || !ship.get_type().is_decoy()) {
+                let hits: usize = player.ship_hits(ship);
+                let sunk: bool = hits == ship.get_cells().len();
+                let status: String = match (sunk, own) {
+                    (true, _) => "sunk".to_string(),
+                    (false, true) => format!("{}/{} hit", hits, ship.get_cells().len()),
+                    (false, false) => "afloat".to_string(),
+                };
+                // sunk ships are grayed out
+                if sunk {
+                    queue!(out, style::SetForegroundColor(style::Color::DarkGrey))?;
+                }
+                queue!(out, cursor::MoveTo(position.0, row), style::Print(format!("{:<12}{}", ship.get_type().to_string(), status)))?;
+                queue!(out, style::SetForegroundColor(style::Color::Reset))?;
+                row += 1;
+            }
+
+            let accuracy: String = tally.accuracy().map_or("-".to_string(), |accuracy| format!("{:.0}%", accuracy * 100.0));
+            queue!(out, cursor::MoveTo(position.0, row + 1), style::Print(format!("Shots fired {}", tally.shots)))?;
+            queue!(out, cursor::MoveTo(position.0, row + 2), style::Print(format!("Accuracy    {}", accuracy)))?;
+            Ok(())
+        }
+
+        /// Render one frame of the game from the point of view of the player in the given seat
+        /// and handle a single key press. Returns the player's action once they have picked one.
+        #[allow(clippy::too_many_arguments)]
+        pub fn show_once(
+            out: &mut render::Renderer, 
+            turn_count: usize,
+            log: &[log::Entry],
+            unlocked: &[achievements::Achievement],
+            players: &mut [player::Player],
+            tallies: &[stats::Tally],
+            seat: usize,
+            targeting: &mut Targeting
+        ) -> std::io::Result<Option<Action>> {
+            // fall back to the cannon once the selected weapon runs out
+            if !players[seat].get_arsenal().can_fire(targeting.weapon) {
+                targeting.weapon = arsenal::Weapon::Cannon;
+            }
+            // stop commanding a ship once it has been hit
+            if let Some(index) = targeting.moving && !players[seat].movable_ships().contains(&index) {
+                targeting.moving = None;
+                targeting.pending_move = None;
+            }
+            // aim somewhere else once the target is out of the game
+            if targeting.target == seat
+                || targeting.target >= players.len()
+                || targeting.allies.contains(&targeting.target)
+                || players[targeting.target].all_ships_sunk() {
+                targeting.next_target(players, seat);
+            }
+            let multiplayer: bool = players.len() > 2;
+
+            let keys: &input::KeyMap = &targeting.keys;
+            let key = |command: input::Command| keys.label(command);
+            let switch_target: String = if multiplayer { format!("{} to switch opponents, ", key(input::Command::SwitchTarget)) } else { String::new() };
+            // the letters of rows that are bound to a command can only be typed while holding shift
+            let shifted: Vec<String> = (b'A'..b'A' + board::ROWS as u8)
+                .map(char::from)
+                .filter(|row| keys.is_bound(event::KeyCode::Char(*row), &input::FIRING))
+                .map(|row| row.to_string())
+                .collect();
+            let typing: String = match shifted.is_empty() {
+                true => "type a cell like B7".to_string(),
+                false => format!("type a cell like B7 (hold Shift for the rows {})", shifted.join("/")),
+            };
+            let moving: String = format!("Use {} or {} to move, {}", keys.labels(&input::DIRECTIONS), typing, switch_target);
+            let quitting: String = format!("{} to save and quit", keys.labels(&[input::Command::SaveGame, input::Command::Back]));
+            let help: String = if targeting.moving.is_some() {
+                format!(
+                    "Use {} to pick a ship, {} to move it, {} to turn it, {} to confirm, {} to go back to firing, {} to save and quit",
+                    key(input::Command::CycleShip),
+                    keys.labels(&input::DIRECTIONS),
+                    key(input::Command::Rotate),
+                    key(input::Command::Confirm),
+                    key(input::Command::MoveShip),
+                    key(input::Command::Back)
+                )
+            } else if players[seat].get_arsenal().has_specials() {
+                format!(
+                    "{}{} to switch weapons, {} to turn the torpedo, {} to fire, {}",
+                    moving, key(input::Command::CycleWeapon), key(input::Command::Rotate), key(input::Command::Fire), quitting
+                )
+            } else if targeting.mobile_fleet {
+                format!(
+                    "{}{} to guess a location on the opponent's board, {} to move a ship instead, {}",
+                    moving, key(input::Command::Fire), key(input::Command::MoveShip), quitting
+                )
+            } else {
+                format!("{}{} to guess a location on the opponent's board, {}", moving, key(input::Command::Fire), quitting)
+            };
+
+            // lay the screen out for the size of the terminal. the help wraps onto as many lines as it
+            // needs, followed by the turn and the cell being typed in, and then the boards, the weapons
+            // and what happened since the player's last turn
+            let (width, _) = terminal_size();
+            let help_lines: Vec<String> = wrap(&help, width);
+            let status_row: u16 = help_lines.len() as u16 + 1;
+            // every board gets a sidebar with the state of its fleet, as long as that doesn't push any board further down
+            let top: u16 = status_row + 3;
+            let mut origins: Vec<(usize, (u16, u16), bool)> = board_origins(players.len(), seat, &targeting.allies, top, width, SIDEBAR_WIDTH);
+            let sidebars: bool = boards_bottom(&origins) <= boards_bottom(&board_origins(players.len(), seat, &targeting.allies, top, width, 0));
+            if !sidebars {
+                origins = board_origins(players.len(), seat, &targeting.allies, top, width, 0);
+            }
+            let weapon_labels: Vec<String> = match players[seat].get_arsenal().has_specials() {
+                true => arsenal::Weapon::iter()
+                    .map(|weapon| match players[seat].get_arsenal().remaining(weapon) {
+                        Some(uses) => format!("{} ({})", weapon, uses),
+                        None => weapon.to_string(),
+                    })
+                    .collect(),
+                false => vec![],
+            };
+            let weapon_positions: Vec<(u16, u16)> = flow(&weapon_labels, 15, width);
+            let weapon_rows: u16 = weapon_positions.last().map_or(0, |position| position.1 + 1);
+            let needed: (u16, u16) = (LABEL_WIDTH + board_width(false), boards_bottom(&origins) + 1 + weapon_rows + 2 + LOG_MIN_ROWS);
+            if too_small(needed) {
+                // leaving saves the game, unless a shot is still being played. the turn is over by then,
+                // so the shot is skipped instead and the game can be saved on the next turn
+                if wait_for_room(out, needed, keys)? {
+                    match targeting.animations.is_empty() {
+                        true => return Ok(Some(Action::SaveAndQuit)),
+                        false => targeting.animations.clear(),
+                    }
+                }
+                return Ok(None);
+            }
+
+            // clear terminal and print the title and movement commands
+            queue!(out, cursor::MoveTo(0, 0), terminal::Clear(terminal::ClearType::All))?;
+            for (i, line) in help_lines.iter().enumerate() {
+                queue!(out, cursor::MoveTo(0, i as u16), style::Print(line))?;
+            }
+            if multiplayer {
+                queue!(out, cursor::MoveTo(0, status_row), style::Print(format!("TURN: {}   {} to play", turn_count, players[seat].get_name())))?;
+            } else {
+                queue!(out, cursor::MoveTo(0, status_row), style::Print(format!("TURN: {}", turn_count)))?;
+            }
+            if !targeting.typed.is_empty() {
+                queue!(out, cursor::MoveTo(0, status_row + 1), style::Print(format!(
+                    "Cell: {}_   ({} to fire, {} to cancel)",
+                    targeting.typed,
+                    key(input::Command::Fire),
+                    key(input::Command::Back)
+                )))?;
+            }
+
+            // preview the cells the selected weapon will cover. cells that have already been
+            // fired at are marked invalid, since the shot would be wasted on them
+            let preview: Vec<(usize, usize)> = match targeting.moving {
+                Some(_) => vec![],
+                None if !targeting.animations.is_empty() => vec![],
+                None => targeting.weapon.area(targeting.cursor, targeting.heading),
+            };
+
+            // preview the ship being moved on the player's own board. the ship is highlighted where it
+            // would end up, or marked invalid where it is if the pending move isn't allowed
+            let mut own_preview: (Vec<(usize, usize)>, board::CellState) = (vec![], board::CellState::Highlighted);
+            if let Some(index) = targeting.moving {
+                let current: Vec<(usize, usize)> = players[seat].get_ships()[index].get_cells().to_vec();
+                own_preview = match targeting.pending_move.map(|m| players[seat].try_move_ship(index, m)) {
+                    None => (current, board::CellState::Highlighted),
+                    Some(Some(destination)) => (destination, board::CellState::Highlighted),
+                    Some(None) => (current, board::CellState::InvalidPlacement),
+                };
+            }
+
+            // apply the previews to the boards, undoing the highlights of the last frame first
+            for r in 0..board::ROWS {
+                for c in 0..board::COLS {
+                    players.iter_mut().for_each(|player| player.get_cell_mut(r, c).undo());
+
+                    let opponent: &mut player::Player = &mut players[targeting.target];
+                    if preview.contains(&(r, c)) {
+                        match opponent.get_public_state(r, c) {
+                            board::CellState::Guessed | board::CellState::HitShip => opponent.get_cell_mut(r, c).invalidate(),
+                            _ => opponent.get_cell_mut(r, c).highlight(),
+                        }
+                    }
+                    if own_preview.0.contains(&(r, c)) {
+                        match own_preview.1 {
+                            board::CellState::InvalidPlacement => players[seat].get_cell_mut(r, c).invalidate(),
+                            _ => players[seat].get_cell_mut(r, c).highlight(),
+                        }
+                    }
+                }
+            }
+
+            // print the player's own board and the boards of their allies, followed by the
+            // boards of their opponents with the ships hidden
+            for (other, origin, small) in origins.iter().copied() {
+                let player: &player::Player = &players[other];
+                let ally: bool = targeting.allies.contains(&other);
+                if other == seat || ally {
+                    draw_board(out, origin, small, targeting.theme, |r, c| player.get_cell(r, c).clone())?;
+                } else {
+                    draw_board(out, origin, small, targeting.theme, |r, c| player.get_hidden_cell(r, c))?;
+                }
+
+                // label the other boards, highlighting the one being aimed at and graying out the ones that are out
+                if small && other != seat {
+                    if other == targeting.target {
+                        queue!(out, style::SetAttribute(style::Attribute::Reverse))?;
+                    }
+                    let label: String = match (player.all_ships_sunk(), ally) {
+                        (true, _) => {
+                            queue!(out, style::SetForegroundColor(style::Color::DarkGrey))?;
+                            format!("{} (out)", player.get_name())
+                        },
+                        (false, true) => format!("{} (ally)", player.get_name()),
+                        (false, false) => player.get_name().to_string(),
+                    };
+                    queue!(out, cursor::MoveTo(origin.0, origin.1 - 2), style::Print(label))?;
+                    queue!(out, style::SetForegroundColor(style::Color::Reset), style::SetAttribute(style::Attribute::NoReverse))?;
+                }
+            }
+
+            if sidebars {
+                for (other, origin, small) in origins.iter().copied() {
+                    let own: bool = other == seat || targeting.allies.contains(&other);
+                    draw_sidebar(out, (origin.0 + board_width(small) + 2, origin.1 - 1), &players[other], &tallies[other], own, !small)?;
+                }
+            }
+
+            // draw the frame of the shot being played over the board it was fired at
+            if let Some(animation) = targeting.animations.first()
+                && let Some((_, origin, small)) = origins.iter().find(|(other, _, _)| *other == animation.get_target()) {
+                for ((r, c), spark) in animation.get_frame() {
+                    let position: (u16, u16) = (origin.0 + (*c as u16) * cell_spacing(*small), origin.1 + *r as u16);
+                    queue!(out, cursor::MoveTo(position.0, position.1), style::Print(targeting.theme.spark(*spark)))?;
+                }
+            }
+
+            // list the weapons and their remaining uses below the boards. weapons that
+            // have run out are grayed out, and the selected weapon is highlighted
+            let mut bottom: u16 = boards_bottom(&origins) + 1;
+            for ((weapon, label), (x, y)) in arsenal::Weapon::iter().zip(weapon_labels.iter()).zip(weapon_positions) {
+                if weapon == targeting.weapon {
+                    queue!(out, style::SetAttribute(style::Attribute::Reverse))?;
+                }
+                if !players[seat].get_arsenal().can_fire(weapon) {
+                    queue!(out, style::SetForegroundColor(style::Color::DarkGrey))?;
+                }
+
+                queue!(out, cursor::MoveTo(x, bottom + y), style::Print(label))?;
+
+                queue!(out, style::SetForegroundColor(style::Color::Reset), style::SetAttribute(style::Attribute::NoReverse))?;
+            }
+            bottom += weapon_rows;
+
+            // print the battle log in the rows left below the weapons, keeping it from scrolling back past its first entry
+            let (_, height) = terminal_size();
+            let log_rows: usize = height.saturating_sub(bottom + 2).max(LOG_MIN_ROWS) as usize;
+            targeting.log_scroll = targeting.log_scroll.min(log.len().saturating_sub(log_rows));
+            let title: String = match targeting.log_scroll {
+                0 => format!("Battle log ({} to scroll back)", targeting.keys.label(input::Command::ScrollLogUp)),
+                _ => format!(
+                    "Battle log ({} to scroll, showing {} lines back)",
+                    targeting.keys.labels(&[input::Command::ScrollLogUp, input::Command::ScrollLogDown]),
+                    targeting.log_scroll
+                ),
+            };
+            queue!(out, cursor::MoveTo(0, bottom + 1), style::PrintStyledContent(style::Stylize::dark_grey(title)))?;
+            let end: usize = log.len() - targeting.log_scroll;
+            for (i, entry) in log[end.saturating_sub(log_rows)..end].iter().enumerate() {
+                let line: String = entry.describe(seat, players).chars().take(width as usize).collect();
+                queue!(out, cursor::MoveTo(0, bottom + 2 + i as u16), style::Print(line))?;
+            }
+
+            // announce any achievements unlocked since the player's last turn over the boards
+            if !unlocked.is_empty() {
+                draw_toast(out, unlocked)?;
+            }
+
+            // write all output to the screen
+            out.flush()?;
+
+            // while a shot is played, the screen is redrawn every tick instead of waiting for a key.
+            // pressing a key skips the shots still waiting to be played
+            if let Some(animation) = targeting.animations.first_mut() {
+                if !event::poll(animation.get_tick())? {
+                    if !animation.advance() {
+                        targeting.animations.remove(0);
+                    }
+                } else if let event::Event::Key(key) = event::read()?
+                    && key.kind == event::KeyEventKind::Press {
+                    targeting.animations.clear();
+                }
+                return Ok(None);
+            }
+
+            // poll for the last event that occurred
+            let event: event::Event = event::read()?;
+
+            // clicking a cell on an opponent's board aims at it and fires, or only aims if the
+            // player wants to confirm with a second click on the same cell
+            if let event::Event::Mouse(mouse) = event
+                && mouse.kind == event::MouseEventKind::Down(event::MouseButton::Left)
+                && targeting.moving.is_none() {
+                let clicked: Option<(usize, (usize, usize))> = origins.into_iter()
+                    .filter(|(other, _, _)| *other != seat && !targeting.allies.contains(other) && !players[*other].all_ships_sunk())
+                    .find_map(|(other, origin, small)| cell_at(origin, small, (mouse.column, mouse.row)).map(|cell| (other, cell)));
+                if let Some((other, cell)) = clicked {
+                    let aimed: bool = targeting.target == other && targeting.cursor == cell;
+                    targeting.target = other;
+                    targeting.cursor = cell;
+                    targeting.typed.clear();
+                    let shot = arsenal::Shot { weapon: targeting.weapon, target: cell, heading: targeting.heading };
+                    if (aimed || !targeting.confirm_clicks) && players[other].can_take_shot(&shot) {
+                        return Ok(Some(Action::Fire(other, shot)));
+                    }
+                }
+                return Ok(None);
+            }
+
+            if let event::Event::Key(key) = event
+                && key.kind == event::KeyEventKind::Press {
+
+                // while commanding a ship, the keys move the ship instead of the cursor
+                if let Some(index) = targeting.moving {
+                    match targeting.keys.command(&key, &input::MOVING) {
+                        Some(input::Command::MoveUp) => targeting.pending_move = Some(ship::ShipMove::Step(ship::ShipOrientation::Up)),
+                        Some(input::Command::MoveDown) => targeting.pending_move = Some(ship::ShipMove::Step(ship::ShipOrientation::Down)),
+                        Some(input::Command::MoveLeft) => targeting.pending_move = Some(ship::ShipMove::Step(ship::ShipOrientation::Left)),
+                        Some(input::Command::MoveRight) => targeting.pending_move = Some(ship::ShipMove::Step(ship::ShipOrientation::Right)),
+                        Some(input::Command::Rotate) => targeting.pending_move = Some(ship::ShipMove::Rotate),
+                        Some(input::Command::CycleShip) => targeting.next_ship(&players[seat]),
+                        // scroll the battle log a page at a time
+                        Some(input::Command::ScrollLogUp) => targeting.log_scroll += log_rows,
+                        Some(input::Command::ScrollLogDown) => targeting.log_scroll = targeting.log_scroll.saturating_sub(log_rows),
+                        Some(input::Command::MoveShip) => {
+                            targeting.moving = None;
+                            targeting.pending_move = None;
+                        },
+                        Some(input::Command::Confirm) => {
+                            // only confirm moves that are allowed
+                            if let Some(ship_move) = targeting.pending_move
+                                && players[seat].try_move_ship(index, ship_move).is_some() {
+                                targeting.moving = None;
+                                targeting.pending_move = None;
+                                return Ok(Some(Action::MoveShip(index, ship_move)));
+                            }
+                        },
+                        Some(input::Command::Back) => return Ok(Some(Action::SaveAndQuit)),
+                        _ => {}
+                    }
+                    return Ok(None);
+                }
+
+                // while a coordinate is being typed in, the fire key fires at it once it's complete
+                if !targeting.typed.is_empty() {
+                    match (targeting.keys.command(&key, &[input::Command::Fire, input::Command::Back]), key.code) {
+                        (Some(input::Command::Fire), _) => {
+                            let typed: String = std::mem::take(&mut targeting.typed);
+                            if board::Board::parse_label(&typed).is_none() {
+                                return Ok(None);
+                            }
+                        },
+                        (Some(_), _) => {
+                            targeting.typed.clear();
+                            return Ok(None);
+                        },
+                        (None, event::KeyCode::Char(c)) => {
+                            targeting.type_char(c);
+                            return Ok(None);
+                        },
+                        (None, event::KeyCode::Backspace) => {
+                            targeting.typed.pop();
+                            return Ok(None);
+                        },
+                        // any other key goes back to moving the cursor around
+                        _ => targeting.typed.clear(),
+                    }
+                }
+
+                // holding shift always types the letter of a row, even if the letter is bound to a command
+                if let event::KeyCode::Char(c) = key.code
+                    && key.modifiers.contains(event::KeyModifiers::SHIFT)
+                    && targeting.type_char(c) {
+                    return Ok(None);
+                }
+
+                let command: Option<input::Command> = targeting.keys.command(&key, &input::FIRING);
+                let cursor_position: &mut (usize, usize) = &mut targeting.cursor;
+                match command {
+                    Some(input::Command::MoveUp) => cursor_position.0 = if cursor_position.0 == 0 { board::ROWS - 1 } else { cursor_position.0 - 1 },
+                    Some(input::Command::MoveDown) => cursor_position.0 = if cursor_position.0 == board::ROWS - 1 { 0 } else { cursor_position.0 + 1 },
+                    // the boards of an opposing team sit side by side, so moving off the edge
+                    // of one board carries the cursor over to the other
+                    Some(input::Command::MoveLeft) if cursor_position.1 == 0 && !targeting.allies.is_empty() => {
+                        cursor_position.1 = board::COLS - 1;
+                        targeting.next_target(players, seat);
+                    },
+                    Some(input::Command::MoveRight) if cursor_position.1 == board::COLS - 1 && !targeting.allies.is_empty() => {
+                        cursor_position.1 = 0;
+                        targeting.next_target(players, seat);
+                    },
+                    Some(input::Command::MoveLeft) => cursor_position.1 = if cursor_position.1 == 0 { board::COLS - 1 } else { cursor_position.1 - 1 },
+                    Some(input::Command::MoveRight) => cursor_position.1 = if cursor_position.1 == board::COLS - 1 { 0 } else { cursor_position.1 + 1 },
+                    // cycle through the weapons that still have uses left
+                    Some(input::Command::CycleWeapon) => targeting.next_weapon(players[seat].get_arsenal()),
+                    Some(input::Command::Rotate) => targeting.heading = targeting.heading.next(),
+                    // cycle through the opponents that are still in the game
+                    Some(input::Command::SwitchTarget) => targeting.next_target(players, seat),
+                    // save the game to continue it later, which leaving the game does as well
+                    Some(input::Command::SaveGame) => return Ok(Some(Action::SaveAndQuit)),
+                    // scroll the battle log a page at a time
+                    Some(input::Command::ScrollLogUp) => targeting.log_scroll += log_rows,
+                    Some(input::Command::ScrollLogDown) => targeting.log_scroll = targeting.log_scroll.saturating_sub(log_rows),
+                    // start commanding a ship, if the rules allow it
+                    Some(input::Command::MoveShip) if targeting.mobile_fleet => targeting.next_ship(&players[seat]),
+                    // start typing in a coordinate with the letter of its row
+                    None => if let event::KeyCode::Char(c) = key.code {
+                        targeting.type_char(c);
+                    },
+                    // shots that would only land on cells already fired at are refused, leaving the turn with the player
+                    Some(input::Command::Fire) => {
+                        let shot = arsenal::Shot { weapon: targeting.weapon, target: targeting.cursor, heading: targeting.heading };
+                        if players[targeting.target].can_take_shot(&shot) {
+                            return Ok(Some(Action::Fire(targeting.target, shot)));
+                        }
+                    },
+                    Some(input::Command::Back) => return Ok(Some(Action::SaveAndQuit)),
+                    _ => {}
+                }
+            } 
+
+            Ok(None)
+        }
+    }
+
+
+    /// Module for playing back a recorded game with both boards fully revealed.
+    pub mod replay_viewer {
+
+        use super::*;
+        use crate::game::{replay, storage};
+
+        /// How long each step is shown for while playing back automatically.
+        const AUTOPLAY_DELAY: std::time::Duration = std::time::Duration::from_millis(700);
+
+        /// Step through the replay until the user goes back.
+        pub fn show(replay: &replay::Replay) -> std::io::Result<()> {
+
+            // enter an alternate screen for the replay
+            terminal::enable_raw_mode()?;
+            let mut out = render::Renderer::stdout();
+            execute!(out, terminal::EnterAlternateScreen, cursor::Hide, terminal::Clear(terminal::ClearType::All))?;
+            let keys: input::KeyMap = input::KeyMap::load();
+            let theme: themes::Theme = themes::Theme::load();
+
+            let total: usize = replay.get_entries().len();
+            let mut step: usize = 0;
+            let mut autoplay: bool = false;
+            // where the replay was exported to, or why it couldn't be
+            let mut exported: Option<String> = None;
+            loop {
+                let (players, description) = replay.play(step);
+
+                // lay the screen out for the size of the terminal, below the help
+                let (width, _) = terminal_size();
+                let help_lines: Vec<String> = wrap(&format!(
+                    "Use {} to step, {} to play or pause, {} to jump, {} to export, {} to go back",
+                    keys.labels(&[input::Command::MoveLeft, input::Command::MoveRight]),
+                    keys.label(input::Command::PlayPause),
+                    keys.labels(&[input::Command::JumpToStart, input::Command::JumpToEnd]),
+                    keys.label(input::Command::ExportReplay),
+                    keys.label(input::Command::Back)
+                ), width);
+                let status_row: u16 = help_lines.len() as u16 + 1;
+                let origins: Vec<(usize, (u16, u16), bool)> = board_origins(players.len(), 0, &[], status_row + 3, width, 0);
+                let needed: (u16, u16) = (LABEL_WIDTH + board_width(false), boards_bottom(&origins) + 1);
+                if too_small(needed) {
+                    if wait_for_room(&mut out, needed, &keys)? {
+                        break;
+                    }
+                    continue;
+                }
+
+                // clear terminal and print the title and movement commands
+                queue!(out, cursor::MoveTo(0, 0), terminal::Clear(terminal::ClearType::All))?;
+                for (i, line) in help_lines.iter().enumerate() {
+                    queue!(out, cursor::MoveTo(0, i as u16), style::Print(line))?;
+                }
+
+                // print the last step along with how far into the game it happened
+                let elapsed: u64 = match step {
+                    0 => 0,
+                    _ => replay.get_entries()[step - 1].time.saturating_sub(replay.started()) / 1000,
+                };
+                let status: String = format!(
+                    "Step {}/{}  [{:02}:{:02}]{}  {}",
+                    step,
+                    total,
+                    elapsed / 60,
+                    elapsed % 60,
+                    if autoplay { "  playing" } else { "" },
+                    description.unwrap_or_default()
+                );
+                queue!(out, cursor::MoveTo(0, status_row), style::Print(status))?;
+                if let Some(exported) = &exported {
+                    queue!(out, cursor::MoveTo(0, status_row - 1), style::Print(exported))?;
+                }
+
+                // print every player's board
+                for (seat, origin, small) in origins {
+                    draw_board(&mut out, origin, small, theme, |r, c| players[seat].get_cell(r, c).clone())?;
+                    queue!(out, cursor::MoveTo(origin.0, origin.1 - 2), style::Print(players[seat].get_name()))?;
+                }
+
+                // write all output to the screen
+                out.flush()?;
+
+                // while playing back automatically, move on to the next step if no key is pressed in time
+                if autoplay && !event::poll(AUTOPLAY_DELAY)? {
+                    step = (step + 1).min(total);
+                    autoplay = step < total;
+                    continue;
+                }
+
+                // poll for the last event that occurred
+                if let event::Event::Key(key) = event::read()?
+                    && key.kind == event::KeyEventKind::Press {
+                    match keys.command(&key, &input::REPLAY) {
+                        Some(input::Command::MoveLeft) => step = step.saturating_sub(1),
+                        Some(input::Command::MoveRight) => step = (step + 1).min(total),
+                        Some(input::Command::Back) => break,
+                        Some(input::Command::JumpToStart) => step = 0,
+                        Some(input::Command::JumpToEnd) => step = total,
+                        // start over when playing from the end
+                        Some(input::Command::PlayPause) => {
+                            autoplay = !autoplay;
+                            if autoplay && step == total {
+                                step = 0;
+                            }
+                        },
+                        // write the game in the text notation, so it can be shared
+                        Some(input::Command::ExportReplay) => {
+                            exported = Some(match storage::export_notation(replay) {
+                                Ok(path) => format!("Exported to {}", path.display()),
+                                Err(err) => format!("Export failed: {}", err),
+                            });
+                        },
+                        _ => {}
+                    }
+                }
+            }
+
+            // leave the replay screen.
+            execute!(out, cursor::Show, terminal::LeaveAlternateScreen)?;
+            terminal::disable_raw_mode()?;
+
+            Ok(())
+        }
+    }
+
+
+    pub mod win_screen {
+
+        use super::*;
+        use crate::game::rating;
+
+        /// Show every player's board, fully revealed, along with the winner of the game, how the
+        /// game changed the players' ratings, how the series it belongs to stands and any achievements
+        /// unlocked during the game.
+        pub fn show(
+            players: &[player::Player],
+            winner: &str,
+            rating_changes: &[rating::Change],
+            standing: Option<&rating::Standing>,
+            unlocked: &[achievements::Achievement]
         ) -> std::io::Result<()> {
 
             // enter an alternate screen for the win screen
             terminal::enable_raw_mode()?;
-            let mut out = std::io::stdout();
+            let mut out = render::Renderer::stdout();
             execute!(out, terminal::EnterAlternateScreen, cursor::Hide, terminal::Clear(terminal::ClearType::All))?;
+            let keys: input::KeyMap = input::KeyMap::load();
+            let theme: themes::Theme = themes::Theme::load();
             
             loop {
+                // lay the screen out for the size of the terminal, below the winner
+                let (width, _) = terminal_size();
+                let origins: Vec<(usize, (u16, u16), bool)> = board_origins(players.len(), 0, &[], 5, width, 0);
+                let needed: (u16, u16) = (LABEL_WIDTH + board_width(false), boards_bottom(&origins) + 1);
+                if too_small(needed) {
+                    if wait_for_room(&mut out, needed, &keys)? {
+                        break;
+                    }
+                    continue;
+                }
+
                 // clear terminal and print the title and movement commands
                 queue!(out, cursor::MoveTo(0, 0), terminal::Clear(terminal::ClearType::All))?;
-                queue!(out, style::Print("Press Esc to quit the game\n\n"))?;
-                queue!(out, style::Print(format!("Winner: {}!\n\n", winner)))?;
+                queue!(out, style::Print(format!("Press {} to quit the game", keys.label(input::Command::Back))))?;
+                queue!(out, cursor::MoveTo(0, 2), style::Print(format!("Winner: {}!", winner)))?;
+                if !rating_changes.is_empty() {
+                    let changes: Vec<String> = rating_changes.iter().map(|change| change.to_string()).collect();
+                    queue!(out, style::Print(format!("   Rating: {}", changes.join(", "))))?;
+                }
+                if let Some(standing) = standing {
+                    queue!(out, cursor::MoveTo(0, 3), style::Print(format!("Series (best of {}): {}", rating::SERIES_LENGTH, standing)))?;
+                }
 
-                // print each cell in the board
-                for r in 0..board::ROWS {
-                    for c in 0..board::COLS {
-                        // print both the player's and opponent's boards
-                        queue!(out, cursor::MoveTo((c as u16) * 3 , (r as u16)  + 4), style::Print(player.get_cell(r, c)))?;
-                        queue!(out, cursor::MoveTo((c as u16) * 3 + OPPONENT_BOARD_OFFSET , (r as u16)  + 4), style::Print(opponent.get_cell(r, c)))?;
+                // print every player's board
+                for (seat, origin, small) in origins {
+                    draw_board(&mut out, origin, small, theme, |r, c| players[seat].get_cell(r, c).clone())?;
+                    if small {
+                        queue!(out, cursor::MoveTo(origin.0, origin.1 - 2), style::Print(players[seat].get_name()))?;
                     }
                 }
 
+                // list the achievements unlocked during the game over the boards
+                if !unlocked.is_empty() {
+                    draw_toast(&mut out, unlocked)?;
+                }
+
                 // write all output to the screen
                 out.flush()?;
 
                 // poll for the last event that occurred
-                if let event::Event::Key(key) = event::read()? {
-                    if key.kind == event::KeyEventKind::Press {
-                        match key.code {
-                            event::KeyCode::Esc => {
-                                break;
-                            },
-                            _ => {}
-                        }
-                    }
-                } 
+                if let event::Event::Key(key) = event::read()?
+                    && key.kind == event::KeyEventKind::Press
+                    && keys.command(&key, &[input::Command::Back]).is_some() {
+                    break;
+                }
             }
 
             // leave the win screen.
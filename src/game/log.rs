@@ -0,0 +1,65 @@
+/// This module contains the battle log, which records everything that happens during a game so the
+/// players can look back at where every shot landed. Entries are kept as events rather than text,
+/// so each player reads them from their own point of view.
+use serde::{Deserialize, Serialize};
+use crate::game::components::{arsenal, board, player, ship};
+use crate::game::themes;
+
+
+/// An enum defining everything that is recorded in the battle log.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub enum Event {
+    /// The player in the first seat fired a shot at the board of the player in the second seat.
+    Fired { seat: usize, target: usize, shot: arsenal::Shot, outcome: arsenal::Outcome },
+    /// The player in the first seat sank a ship of the player in the second seat.
+    Sank { seat: usize, target: usize, ship: ship::ShipType },
+    /// The player in the given seat moved one of their ships instead of firing.
+    Moved { seat: usize, ship: ship::ShipType },
+    /// The player in the given seat lost their last ship.
+    Out { seat: usize },
+}
+
+/// A struct holding an event along with the turn it happened on.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Entry {
+    pub turn: usize,
+    pub event: Event,
+}
+
+impl Entry {
+    /// Describe the entry to the player in the given seat, who is called "you". The seat being fired
+    /// at is only named when there are more than two players, and ships that are moved are only named
+    /// to their owner.
+    pub fn describe(&self, viewer: usize, players: &[player::Player]) -> String {
+        let subject = |seat: usize| match seat == viewer {
+            true => "You".to_string(),
+            false => players[seat].get_name().to_string(),
+        };
+        let owner = |seat: usize| match seat == viewer {
+            true => "your".to_string(),
+            false => format!("{}'s", players[seat].get_name()),
+        };
+        let dash: &str = if themes::is_ascii() { "-" } else { "—" };
+
+        let text: String = match self.event {
+            Event::Fired { seat, target, shot, outcome } => {
+                let cell: String = board::Board::label(shot.target.0, shot.target.1);
+                let at: String = match players.len() {
+                    2 => cell,
+                    _ => format!("{} {}", owner(target), cell),
+                };
+                match shot.weapon {
+                    arsenal::Weapon::Cannon => format!("{} fired at {} {} {}", subject(seat), at, dash, outcome),
+                    arsenal::Weapon::Sonar => format!("{} pinged {} with sonar {} {}", subject(seat), at, dash, outcome),
+                    weapon => format!("{} fired a {} at {} {} {}", subject(seat), weapon.to_string().to_lowercase(), at, dash, outcome),
+                }
+            },
+            Event::Sank { seat, target, ship } => format!("{} sank {} {}", subject(seat), owner(target), ship),
+            Event::Moved { seat, ship } if seat == viewer => format!("You moved your {}", ship),
+            Event::Moved { seat, ship: _ } => format!("{} moved a ship", subject(seat)),
+            Event::Out { seat } if seat == viewer => "You are out of the game".to_string(),
+            Event::Out { seat } => format!("{} is out of the game", subject(seat)),
+        };
+        format!("Turn {}: {}", self.turn, text)
+    }
+}
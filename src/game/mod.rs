@@ -1,3 +1,18 @@
+pub mod achievements;
+pub mod animation;
 pub mod components;
+pub mod formations;
+#[allow(clippy::module_inception)]
 pub mod game;
-pub mod layouts;
\ No newline at end of file
+pub mod input;
+pub mod layouts;
+pub mod log;
+pub mod notation;
+pub mod rating;
+pub mod render;
+pub mod replay;
+pub mod rules;
+pub mod settings;
+pub mod stats;
+pub mod storage;
+pub mod themes;
\ No newline at end of file
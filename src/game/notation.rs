@@ -0,0 +1,588 @@
+/// This module contains a human readable text notation for recorded games, so they can be
+/// shared in chat or kept next to code. A game written in the notation looks like this:
+///
+/// ```text
+/// Mode: Classic
+/// Difficulty: Normal
+/// Teams: no
+/// Players: Player, Computer
+/// Date: 2024-05-17 18:03:07.412
+/// Fleet: C5 B4 D3 S3 P2
+///
+/// [Player]
+/// C A1-A5
+/// B C3-F3
+/// ...
+///
+/// 1. B7 hit {4.210}; E3 miss {9.872}
+/// 2. bomb C4 1 hit 8 misses {15.003}; move 2 up {21.448}
+/// ```
+///
+/// Straight ships are written from their first cell to their last, while other shapes are written
+/// with their first cell, the way they face and whether they are flipped, like `L B2 down flipped`.
+/// Every numbered line is one round, with the moves of each player still in the game in seat order.
+/// With more than two players, shots name the seat they are aimed at, like `@3 B7 hit`. Every move
+/// ends with when it was made, in seconds since the game started, which can be left out.
+use std::vec;
+use crate::game::components::{ai, arsenal, board, player, ship};
+use crate::game::{replay, rules};
+
+
+/// An error returned when a game written in the notation could not be read.
+#[derive(Debug, thiserror::Error)]
+#[error("line {line}: {message}")]
+pub struct NotationError {
+    pub line: usize,
+    pub message: String,
+}
+
+/// Create an error for the given line.
+fn error(line: usize, message: impl Into<String>) -> NotationError {
+    NotationError { line, message: message.into() }
+}
+
+/// Get the name of a direction, as written in the notation.
+fn direction_name(direction: ship::ShipOrientation) -> &'static str {
+    match direction {
+        ship::ShipOrientation::Left => "left",
+        ship::ShipOrientation::Up => "up",
+        ship::ShipOrientation::Right => "right",
+        ship::ShipOrientation::Down => "down",
+    }
+}
+
+/// Parse the name of a direction.
+fn parse_direction(text: &str) -> Option<ship::ShipOrientation> {
+    match text.to_ascii_lowercase().as_str() {
+        "left" => Some(ship::ShipOrientation::Left),
+        "up" => Some(ship::ShipOrientation::Up),
+        "right" => Some(ship::ShipOrientation::Right),
+        "down" => Some(ship::ShipOrientation::Down),
+        _ => None,
+    }
+}
+
+/// Describe the outcome of a shot, as written in the notation.
+fn outcome_text(outcome: &arsenal::Outcome) -> String {
+    let plural = |count: usize, word: &str, suffix: &str| match count {
+        1 => format!("{} {}", count, word),
+        _ => format!("{} {}{}", count, word, suffix),
+    };
+    match outcome {
+        arsenal::Outcome::Strike { hits: 0, misses: 1 } => "miss".to_string(),
+        arsenal::Outcome::Strike { hits: 1, misses: 0 } => "hit".to_string(),
+        arsenal::Outcome::Strike { hits, misses } => format!("{} {}", plural(*hits, "hit", "s"), plural(*misses, "miss", "es")),
+        arsenal::Outcome::Sonar(true) => "contact".to_string(),
+        arsenal::Outcome::Sonar(false) => "no contact".to_string(),
+    }
+}
+
+/// Write when a move was made, given in milliseconds since the game started, like "{12.345}".
+fn elapsed_text(millis: u64) -> String {
+    format!("{{{}.{:03}}}", millis / 1000, millis % 1000)
+}
+
+/// Parse when a move was made, written like "{12.345}", into milliseconds since the game started.
+fn parse_elapsed(text: &str) -> Option<u64> {
+    let seconds: &str = text.strip_prefix('{')?.strip_suffix('}')?.trim();
+    let (seconds, millis) = seconds.split_once('.').unwrap_or((seconds, "000"));
+    if millis.len() != 3 {
+        return None;
+    }
+    Some(seconds.parse::<u64>().ok()? * 1000 + millis.parse::<u64>().ok()?)
+}
+
+/// Returns true if the ship lies in a straight line, so it can be written from its first cell to its last.
+fn is_straight(ship_type: ship::ShipType) -> bool {
+    ship_type.shape().iter().all(|(r, _)| *r == 0)
+}
+
+/// Describe the ships that make up a fleet, like "C5 B4 D3 S3 P2".
+fn fleet_text(fleet: &[ship::ShipType]) -> String {
+    fleet.iter().map(|ship_type| format!("{}{}", ship_type.symbol(), ship_type.size())).collect::<vec::Vec<String>>().join(" ")
+}
+
+/// Write a game in the notation.
+pub fn export(replay: &replay::Replay) -> String {
+    let game_rules: &rules::GameRules = replay.get_rules();
+    let names: &[String] = replay.get_names();
+    let mut lines: vec::Vec<String> = vec![
+        format!("Mode: {}", game_rules.mode),
+        format!("Difficulty: {}", game_rules.difficulty),
+        format!("Teams: {}", if game_rules.teams { "yes" } else { "no" }),
+        format!("Players: {}", names.join(", ")),
+        format!("Date: {}", replay::format_exact_time(replay.started())),
+        format!("Fleet: {}", fleet_text(&game_rules.fleet())),
+    ];
+
+    // write the ships of every player in a section of their own
+    for (seat, name) in names.iter().enumerate() {
+        lines.push(String::new());
+        lines.push(format!("[{}]", name));
+        for entry in replay.get_entries() {
+            if let replay::Event::Place { seat: placed_by, ship_type, cells, orientation, mirrored } = &entry.event
+                && *placed_by == seat {
+                let anchor: (usize, usize) = cells[0];
+                if is_straight(*ship_type) {
+                    let end: (isize, isize) = *ship_type.footprint(anchor, *orientation, false).last().unwrap_or(&(0, 0));
+                    let end: String = board::Board::label(end.0 as usize, end.1 as usize);
+                    lines.push(format!("{} {}-{}", ship_type.symbol(), board::Board::label(anchor.0, anchor.1), end));
+                } else {
+                    let flipped: &str = if *mirrored { " flipped" } else { "" };
+                    lines.push(format!("{} {} {}{}", ship_type.symbol(), board::Board::label(anchor.0, anchor.1), direction_name(*orientation), flipped));
+                }
+            }
+        }
+    }
+
+    // play the game back to find the outcome of every shot, starting a new round
+    // whenever the seats wrap back around
+    let mut players: vec::Vec<player::Player> = names.iter().map(|name| player::Player::new(name)).collect();
+    let mut rounds: vec::Vec<vec::Vec<String>> = vec![];
+    let mut last_seat: Option<usize> = None;
+    for entry in replay.get_entries() {
+        let (seat, text) = match &entry.event {
+            replay::Event::Place { seat, ship_type, cells, orientation, mirrored } => {
+                players[*seat].add_ship(cells.clone(), *ship_type, *orientation, *mirrored);
+                continue;
+            },
+            replay::Event::Fire { seat, target, shot } => {
+                let outcome: arsenal::Outcome = players[*target].take_shot(shot);
+                let mut parts: vec::Vec<String> = vec![];
+                if names.len() > 2 {
+                    parts.push(format!("@{}", target + 1));
+                }
+                if shot.weapon != arsenal::Weapon::Cannon {
+                    parts.push(shot.weapon.to_string().to_ascii_lowercase());
+                }
+                parts.push(board::Board::label(shot.target.0, shot.target.1));
+                if shot.weapon == arsenal::Weapon::Torpedo {
+                    parts.push(direction_name(shot.heading).to_string());
+                }
+                parts.push(outcome_text(&outcome));
+                (*seat, parts.join(" "))
+            },
+            replay::Event::Move { seat, index, ship_move } => {
+                players[*seat].move_ship(*index, *ship_move);
+                let direction: &str = match ship_move {
+                    ship::ShipMove::Step(direction) => direction_name(*direction),
+                    ship::ShipMove::Rotate => "turn",
+                };
+                (*seat, format!("move {} {}", index + 1, direction))
+            },
+        };
+        if last_seat.is_none_or(|last| seat <= last) {
+            rounds.push(vec![]);
+        }
+        last_seat = Some(seat);
+        if let Some(round) = rounds.last_mut() {
+            round.push(format!("{} {}", text, elapsed_text(entry.time.saturating_sub(replay.started()))));
+        }
+    }
+
+    lines.push(String::new());
+    for (i, round) in rounds.iter().enumerate() {
+        lines.push(format!("{}. {}", i + 1, round.join("; ")));
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+/// The headers read at the top of a game written in the notation.
+#[derive(Default)]
+struct Headers {
+    mode: Option<rules::GameMode>,
+    difficulty: Option<ai::Difficulty>,
+    teams: bool,
+    names: vec::Vec<String>,
+    date: Option<u64>,
+    fleet: Option<String>,
+}
+
+/// A game being rebuilt from the notation, checking every placement and move along the way.
+struct Reader {
+    rules: rules::GameRules,
+    names: vec::Vec<String>,
+    players: vec::Vec<player::Player>,
+    // when the game started, which is when every ship was placed
+    started: u64,
+    entries: vec::Vec<replay::Entry>,
+}
+
+impl Reader {
+    /// Start a game with the players named in the headers.
+    fn new(line: usize, headers: &Headers) -> Result<Self, NotationError> {
+        let mode: rules::GameMode = headers.mode.ok_or_else(|| error(line, "the game mode is missing"))?;
+        let mut game_rules: rules::GameRules = rules::GameRules::new(mode, headers.difficulty.unwrap_or(ai::Difficulty::Normal));
+        game_rules.teams = headers.teams;
+
+        if headers.names.len() < 2 {
+            return Err(error(line, "a game needs at least two players"));
+        }
+        if let Some(fleet) = &headers.fleet
+            && *fleet != fleet_text(&game_rules.fleet()) {
+            return Err(error(line, format!("the {} mode is played with the fleet {}", mode, fleet_text(&game_rules.fleet()))));
+        }
+
+        let players: vec::Vec<player::Player> = headers.names.iter()
+            .map(|name| {
+                let mut player: player::Player = player::Player::new(name);
+                player.set_arsenal(game_rules.arsenal());
+                player
+            })
+            .collect();
+        let started: u64 = headers.date.unwrap_or_else(replay::now);
+        Ok(Self { rules: game_rules, names: headers.names.clone(), players, started, entries: vec![] })
+    }
+
+    /// Place a ship, written like `C A1-A5` or `L B2 down flipped`, for the player in the given seat.
+    fn place(&mut self, line: usize, seat: usize, text: &str) -> Result<(), NotationError> {
+        let tokens: vec::Vec<&str> = text.split_whitespace().collect();
+        let symbol: char = tokens.first().and_then(|token| token.chars().next()).ok_or_else(|| error(line, "expected a ship"))?;
+        let ship_type: ship::ShipType = self.rules.fleet().into_iter()
+            .find(|ship_type| ship_type.symbol() == symbol)
+            .ok_or_else(|| error(line, format!("there is no ship '{}' in the {} fleet", symbol, self.rules.mode)))?;
+        let position: &str = tokens.get(1).ok_or_else(|| error(line, "expected where the ship is placed"))?;
+        let invalid = || error(line, format!("'{}' is not a valid position", position));
+
+        let (anchor, orientation, mirrored, end) = match position.split_once('-') {
+            Some((from, to)) => {
+                let anchor: (usize, usize) = board::Board::parse_label(from).ok_or_else(invalid)?;
+                let end: (usize, usize) = board::Board::parse_label(to).ok_or_else(invalid)?;
+                let orientation: ship::ShipOrientation = match (anchor, end) {
+                    ((r1, c1), (r2, c2)) if r1 == r2 && c2 > c1 => ship::ShipOrientation::Right,
+                    ((r1, c1), (r2, c2)) if r1 == r2 && c2 < c1 => ship::ShipOrientation::Left,
+                    ((r1, c1), (r2, c2)) if c1 == c2 && r2 > r1 => ship::ShipOrientation::Down,
+                    ((r1, c1), (r2, c2)) if c1 == c2 && r2 < r1 => ship::ShipOrientation::Up,
+                    _ => return Err(error(line, "a ship has to lie in a straight line")),
+                };
+                (anchor, orientation, false, Some(end))
+            },
+            None => {
+                let anchor: (usize, usize) = board::Board::parse_label(position).ok_or_else(invalid)?;
+                let orientation: ship::ShipOrientation = tokens.get(2)
+                    .and_then(|token| parse_direction(token))
+                    .ok_or_else(|| error(line, "expected the way the ship faces, like 'right'"))?;
+                let mirrored: bool = tokens.get(3).is_some_and(|token| token.eq_ignore_ascii_case("flipped"));
+                (anchor, orientation, mirrored, None)
+            },
+        };
+
+        let cells: vec::Vec<(usize, usize)> = self.players[seat].try_place_ship(anchor.0, anchor.1, orientation, mirrored, ship_type)
+            .ok_or_else(|| error(line, format!("the {} doesn't fit there, or overlaps another ship", ship_type)))?;
+        if let Some(end) = end && cells.last() != Some(&end) {
+            return Err(error(line, format!("the {} is {} cells long", ship_type, ship_type.size())));
+        }
+
+        self.players[seat].add_ship(cells.clone(), ship_type, orientation, mirrored);
+        self.entries.push(replay::Entry { time: self.started, event: replay::Event::Place { seat, ship_type, cells, orientation, mirrored } });
+        Ok(())
+    }
+
+    /// Check that every player has placed exactly the ships of the fleet.
+    fn check_fleets(&self, line: usize) -> Result<(), NotationError> {
+        let mut fleet: vec::Vec<char> = self.rules.fleet().iter().map(|ship_type| ship_type.symbol()).collect();
+        fleet.sort();
+        for (seat, player) in self.players.iter().enumerate() {
+            let mut placed: vec::Vec<char> = player.get_ships().iter().map(|ship| ship.get_type().symbol()).collect();
+            placed.sort();
+            if placed != fleet {
+                return Err(error(line, format!("{} has to place the ships {}", self.names[seat], fleet_text(&self.rules.fleet()))));
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the next seat after the given one, in this round, that still has ships afloat.
+    fn next_seat(&self, after: Option<usize>) -> Option<usize> {
+        let start: usize = after.map_or(0, |seat| seat + 1);
+        (start..self.players.len()).find(|seat| !self.players[*seat].all_ships_sunk())
+    }
+
+    /// Returns true if only one team still has ships afloat.
+    fn is_over(&self) -> bool {
+        let mut teams = (0..self.players.len())
+            .filter(|seat| !self.players[*seat].all_ships_sunk())
+            .map(|seat| self.rules.team_of(seat));
+        let first: Option<usize> = teams.next();
+        teams.all(|team| Some(team) == first)
+    }
+
+    /// Apply a single move, written like `B7 hit`, `@3 torpedo A4 down miss` or `move 2 turn`,
+    /// for the player in the given seat.
+    fn play(&mut self, line: usize, seat: usize, text: &str) -> Result<(), NotationError> {
+        // moves without a time are taken to be made right after the last one
+        let last: u64 = self.entries.last().map_or(self.started, |entry| entry.time);
+        let (text, time): (&str, u64) = match text.find('{') {
+            Some(start) => {
+                let elapsed: u64 = parse_elapsed(&text[start..])
+                    .ok_or_else(|| error(line, format!("'{}' is not a time, like '{{12.345}}'", &text[start..])))?;
+                (text[..start].trim(), self.started + elapsed)
+            },
+            None => (text, last),
+        };
+        if time < last {
+            return Err(error(line, format!("'{}' was made before the move before it", text)));
+        }
+
+        let mut tokens: vec::Vec<&str> = text.split_whitespace().collect();
+        if tokens.is_empty() {
+            return Err(error(line, "expected a move"));
+        }
+
+        // moving a ship instead of firing
+        if tokens[0].eq_ignore_ascii_case("move") {
+            let index: usize = tokens.get(1)
+                .and_then(|token| token.parse::<usize>().ok())
+                .and_then(|index| index.checked_sub(1))
+                .ok_or_else(|| error(line, "expected the number of the ship to move"))?;
+            let ship_move: ship::ShipMove = match tokens.get(2) {
+                Some(token) if token.eq_ignore_ascii_case("turn") => ship::ShipMove::Rotate,
+                Some(token) => ship::ShipMove::Step(parse_direction(token).ok_or_else(|| error(line, "expected a direction or 'turn'"))?),
+                None => return Err(error(line, "expected a direction or 'turn'")),
+            };
+            if !self.rules.ships_can_move() || !self.players[seat].move_ship(index, ship_move) {
+                return Err(error(line, format!("{} can't make the move '{}'", self.names[seat], text)));
+            }
+            self.entries.push(replay::Entry { time, event: replay::Event::Move { seat, index, ship_move } });
+            return Ok(());
+        }
+
+        // find who is being fired at. with two players, it can only be the other one
+        let target: usize = match tokens[0].strip_prefix('@') {
+            Some(number) => {
+                tokens.remove(0);
+                number.parse::<usize>().ok()
+                    .and_then(|number| number.checked_sub(1))
+                    .filter(|target| *target < self.players.len())
+                    .ok_or_else(|| error(line, format!("there is no seat '{}'", number)))?
+            },
+            None if self.players.len() == 2 => 1 - seat,
+            None => return Err(error(line, "with more than two players, every shot has to name its target, like '@2 B7'")),
+        };
+        if self.rules.team_of(target) == self.rules.team_of(seat) || self.players[target].all_ships_sunk() {
+            return Err(error(line, format!("{} can't fire at {}", self.names[seat], self.names[target])));
+        }
+
+        let weapon: arsenal::Weapon = match arsenal::Weapon::iter().find(|weapon| tokens.first().is_some_and(|token| token.eq_ignore_ascii_case(&weapon.to_string()))) {
+            Some(weapon) => {
+                tokens.remove(0);
+                weapon
+            },
+            None => arsenal::Weapon::Cannon,
+        };
+        if !self.players[seat].get_arsenal().can_fire(weapon) {
+            return Err(error(line, format!("{} has no {} left", self.names[seat], weapon)));
+        }
+
+        let cell: &str = tokens.first().ok_or_else(|| error(line, "expected a cell to fire at"))?;
+        let cell: (usize, usize) = board::Board::parse_label(cell).ok_or_else(|| error(line, format!("'{}' is not a cell on the board", cell)))?;
+        tokens.remove(0);
+        let heading: ship::ShipOrientation = match weapon {
+            arsenal::Weapon::Torpedo => {
+                let heading = tokens.first().and_then(|token| parse_direction(token))
+                    .ok_or_else(|| error(line, "expected the direction the torpedo travels in"))?;
+                tokens.remove(0);
+                heading
+            },
+            _ => ship::ShipOrientation::Right,
+        };
+
+        // whatever is left is the outcome, which has to match what actually happened. shots that only
+        // cover cells already fired at are refused during a game, so they can't have happened
+        let shot = arsenal::Shot { weapon, target: cell, heading };
+        if !self.players[target].can_take_shot(&shot) {
+            return Err(error(line, format!("{} only covers cells that were already fired at", shot)));
+        }
+        let outcome: arsenal::Outcome = self.players[target].take_shot(&shot);
+        self.players[seat].get_arsenal_mut().consume(weapon);
+        let written: String = tokens.join(" ").to_ascii_lowercase();
+        if !written.is_empty() && written != outcome_text(&outcome) {
+            return Err(error(line, format!("{} was '{}', not '{}'", shot, outcome_text(&outcome), written)));
+        }
+
+        self.entries.push(replay::Entry { time, event: replay::Event::Fire { seat, target, shot } });
+        Ok(())
+    }
+}
+
+/// Read a game written in the notation. Every placement is checked against the board and every
+/// shot is played out, so games that couldn't have happened are rejected.
+pub fn import(text: &str) -> Result<replay::Replay, NotationError> {
+    let mut headers: Headers = Headers::default();
+    let mut reader: Option<Reader> = None;
+    let mut section: Option<usize> = None;
+    let mut moves_started: bool = false;
+    let mut round: usize = 0;
+
+    for (i, raw) in text.lines().enumerate() {
+        let line: usize = i + 1;
+        let text: &str = raw.trim();
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+
+        // a numbered round of moves
+        if let Some((number, moves)) = text.split_once(". ")
+            && let Ok(number) = number.parse::<usize>() {
+            let game: &mut Reader = reader.as_mut().ok_or_else(|| error(line, "moves have to come after the ships are placed"))?;
+            if !moves_started {
+                game.check_fleets(line)?;
+                moves_started = true;
+            }
+            round += 1;
+            if number != round {
+                return Err(error(line, format!("expected round {}", round)));
+            }
+
+            let mut seat: Option<usize> = None;
+            for text in moves.split(';') {
+                if game.is_over() {
+                    return Err(error(line, "the game is already over"));
+                }
+                seat = game.next_seat(seat);
+                let current: usize = seat.ok_or_else(|| error(line, "every player has already moved this round"))?;
+                game.play(line, current, text.trim())?;
+            }
+            continue;
+        }
+
+        if moves_started {
+            return Err(error(line, "expected a numbered round of moves"));
+        }
+
+        // the start of a player's placements
+        if let Some(name) = text.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            if reader.is_none() {
+                reader = Some(Reader::new(line, &headers)?);
+            }
+            section = Some(headers.names.iter().position(|other| other == name)
+                .ok_or_else(|| error(line, format!("there is no player named '{}'", name)))?);
+            continue;
+        }
+
+        // a ship placement for the current player
+        if let (Some(game), Some(seat)) = (reader.as_mut(), section) {
+            game.place(line, seat, text)?;
+            continue;
+        }
+
+        // otherwise, it has to be a header
+        let (key, value) = text.split_once(':').ok_or_else(|| error(line, format!("unexpected '{}'", text)))?;
+        let value: &str = value.trim();
+        match key.trim().to_ascii_lowercase().as_str() {
+            "mode" => headers.mode = Some(rules::GameMode::iter().find(|mode| mode.to_string().eq_ignore_ascii_case(value))
+                .ok_or_else(|| error(line, format!("there is no game mode '{}'", value)))?),
+            "difficulty" => headers.difficulty = Some(ai::Difficulty::iter().find(|difficulty| difficulty.to_string().eq_ignore_ascii_case(value))
+                .ok_or_else(|| error(line, format!("there is no difficulty '{}'", value)))?),
+            "teams" => headers.teams = value.eq_ignore_ascii_case("yes"),
+            "players" => headers.names = value.split(',').map(|name| name.trim().to_string()).collect(),
+            "date" => headers.date = Some(replay::parse_time(value).ok_or_else(|| error(line, format!("'{}' is not a date", value)))?),
+            "fleet" => headers.fleet = Some(value.to_string()),
+            _ => return Err(error(line, format!("unknown header '{}'", key))),
+        }
+    }
+
+    let game: Reader = reader.ok_or_else(|| error(text.lines().count(), "no ships were placed"))?;
+    if !moves_started {
+        game.check_fleets(text.lines().count())?;
+    }
+    Ok(replay::Replay::from_entries(game.rules, game.names, game.entries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write a classic game between Ann and Bob, with the given round of moves at the end.
+    fn game_text(last_round: &str) -> String {
+        let fleet: [&str; 5] = ["C A1-A5", "B B1-B4", "D C1-C3", "S D1-D3", "P E1-E2"];
+        let mut lines: vec::Vec<String> = vec!["Mode: Classic".to_string(), "Players: Ann, Bob".to_string()];
+        for name in ["Ann", "Bob"] {
+            lines.push(String::new());
+            lines.push(format!("[{}]", name));
+            lines.extend(fleet.iter().map(|ship| ship.to_string()));
+        }
+        lines.push(String::new());
+        lines.push("1. A1 hit; J10 miss".to_string());
+        lines.push(last_round.to_string());
+        lines.join("\n")
+    }
+
+    /// Place the fleet of the given rules for Ann and Bob, every ship facing right from the given
+    /// anchors. The T and the block are flipped, since they look the same flipped but cover other cells.
+    fn place_fleets(game_rules: &rules::GameRules, anchors: &[(usize, usize)]) -> vec::Vec<player::Player> {
+        let mut players: vec::Vec<player::Player> = vec![player::Player::new("Ann"), player::Player::new("Bob")];
+        for player in players.iter_mut() {
+            for (ship_type, (r, c)) in game_rules.fleet().into_iter().zip(anchors.iter().copied()) {
+                let mirrored: bool = matches!(ship_type, ship::ShipType::TShip(_, _) | ship::ShipType::BlockShip(_, _));
+                let cells: vec::Vec<(usize, usize)> = player.try_place_ship(r, c, ship::ShipOrientation::Right, mirrored, ship_type)
+                    .expect("the fleet should fit on the board");
+                player.add_ship(cells, ship_type, ship::ShipOrientation::Right, mirrored);
+            }
+        }
+        players
+    }
+
+    #[test]
+    fn an_exported_game_is_imported_as_it_was_played() -> Result<(), NotationError> {
+        let game_rules: rules::GameRules = rules::GameRules::new(rules::GameMode::Classic, ai::Difficulty::Normal);
+        let mut played: replay::Replay = replay::Replay::new(game_rules, &place_fleets(&game_rules, &[(0, 0), (1, 0), (2, 0), (3, 0), (4, 0)]));
+        for (seat, target) in [(0, (0, 0)), (1, (9, 9)), (0, (0, 1)), (1, (9, 8))] {
+            // leave time between the moves, so they aren't all recorded at the same time
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            played.push(replay::Event::Fire { seat, target: 1 - seat, shot: arsenal::Shot { weapon: arsenal::Weapon::Cannon, target, heading: ship::ShipOrientation::Right } });
+        }
+
+        let imported: replay::Replay = import(&export(&played))?;
+        assert!(imported.get_entries() == played.get_entries(), "the game changed on the way through the notation");
+        assert_eq!(imported.get_names(), played.get_names());
+        Ok(())
+    }
+
+    #[test]
+    fn flipped_ships_are_imported_where_they_were_placed() -> Result<(), NotationError> {
+        let game_rules: rules::GameRules = rules::GameRules::new(rules::GameMode::TetrisFleet, ai::Difficulty::Normal);
+        let placed: replay::Replay = replay::Replay::new(game_rules, &place_fleets(&game_rules, &[(1, 1), (4, 0), (6, 6), (8, 1), (9, 5)]));
+
+        let imported: replay::Replay = import(&export(&placed))?;
+        assert!(imported.get_entries() == placed.get_entries(), "flipped ships moved on the way through the notation");
+        Ok(())
+    }
+
+    #[test]
+    fn dates_written_to_the_minute_are_still_read() -> Result<(), NotationError> {
+        let text: String = game_text("2. A2 hit").replacen("Players:", "Date: 2024-05-17 18:03\nPlayers:", 1);
+        assert_eq!(import(&text)?.started(), 1_715_968_980_000);
+        Ok(())
+    }
+
+    #[test]
+    fn overlapping_ships_are_rejected() {
+        let text: String = game_text("2. A2 hit").replacen("B B1-B4", "B A2-D2", 1);
+        assert_eq!(import(&text).err().map(|err| err.line), Some(6));
+    }
+
+    #[test]
+    fn ships_off_the_board_are_rejected() {
+        let text: String = game_text("2. A2 hit").replacen("P E1-E2", "P E10 right", 1);
+        assert_eq!(import(&text).err().map(|err| err.line), Some(9));
+    }
+
+    #[test]
+    fn a_shot_at_cells_already_fired_at_is_rejected() {
+        assert_eq!(import(&game_text("2. A1 hit")).err().map(|err| err.line), Some(19));
+    }
+
+    #[test]
+    fn a_move_made_before_the_last_one_is_rejected() {
+        let text: String = game_text("2. A2 hit {3.000}; J9 miss {2.500}");
+        assert_eq!(import(&text).err().map(|err| err.line), Some(19));
+    }
+
+    #[test]
+    fn a_wrong_outcome_is_rejected() {
+        assert_eq!(import(&game_text("2. A2 miss")).err().map(|err| err.line), Some(19));
+        assert!(import(&game_text("2. A2 hit")).is_ok());
+    }
+}
@@ -0,0 +1,208 @@
+/// This module contains logic for rating profiles against the computer and each other, using the Elo
+/// system, and for keeping track of the series played between the same two opponents.
+use std::{fmt, vec};
+use serde::{Deserialize, Serialize};
+use crate::game::components::ai;
+use crate::game::game;
+
+
+/// The rating every profile starts out with.
+pub const INITIAL_RATING: i32 = 1200;
+
+/// The most points a single game can be worth.
+const K_FACTOR: f64 = 32.0;
+
+/// How many games a series between the same two opponents is played over. Whoever wins most of
+/// them takes the series, which is worth as many points again as the game that decided it.
+pub const SERIES_LENGTH: u32 = 3;
+
+/// Get the rating a computer of the given difficulty starts out with, so that beating a harder
+/// computer is worth more from the very first game.
+fn initial_computer_rating(difficulty: ai::Difficulty) -> i32 {
+    match difficulty {
+        ai::Difficulty::Easy => 800,
+        ai::Difficulty::Normal => 1200,
+        ai::Difficulty::Hard => 1600,
+    }
+}
+
+/// Get the points the winner of a game takes from the loser. Ratings are whole numbers and the
+/// loser gives up exactly what the winner gains, so anyone working the update out from the same
+/// ratings, like both ends of a network game, comes to the same result.
+pub fn points_won(winner: i32, loser: i32) -> i32 {
+    let expected: f64 = 1.0 / (1.0 + 10f64.powf((loser - winner) as f64 / 400.0));
+    (K_FACTOR * (1.0 - expected)).round() as i32
+}
+
+/// An enum defining who can hold a rating.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub enum Entrant {
+    Profile(String),
+    Computer(ai::Difficulty),
+}
+
+impl fmt::Display for Entrant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Entrant::Profile(name) => write!(f, "{}", name),
+            Entrant::Computer(difficulty) => write!(f, "Computer ({})", difficulty),
+        }
+    }
+}
+
+/// A struct describing how one rating changed after a game.
+#[derive(Clone)]
+pub struct Change {
+    pub name: String,
+    pub before: i32,
+    pub after: i32,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} ({:+})", self.name, self.after, self.after - self.before)
+    }
+}
+
+/// A struct holding how a series between two opponents stands, as the games each of them has won.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Standing {
+    entrants: [Entrant; 2],
+    wins: [u32; 2],
+}
+
+impl Standing {
+    /// Get whoever has won the series, once either of them has won most of its games.
+    pub fn winner(&self) -> Option<&Entrant> {
+        (0..2).find(|side| self.wins[*side] > SERIES_LENGTH / 2).map(|side| &self.entrants[side])
+    }
+}
+
+impl fmt::Display for Standing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} - {} {}", self.entrants[0], self.wins[0], self.wins[1], self.entrants[1])?;
+        match self.winner() {
+            Some(winner) => write!(f, ", {} takes the series", winner),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A struct holding the series being played between every pair of opponents. A series is forgotten
+/// once it has been decided, so the next game between the same opponents starts a new one.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Series {
+    standings: vec::Vec<Standing>,
+}
+
+impl Series {
+    /// Forget every series a profile is playing.
+    pub fn remove_profile(&mut self, name: &str) {
+        let profile: Entrant = Entrant::Profile(name.to_string());
+        self.standings.retain(|standing| !standing.entrants.contains(&profile));
+    }
+
+    /// Add a game won by one of the given opponents to the series between them, whichever of them
+    /// went first, and return how the series stands.
+    fn record(&mut self, entrants: &[Entrant; 2], winner: usize) -> Standing {
+        let mut standing: Standing = match self.standings.iter().position(|standing| entrants.iter().all(|entrant| standing.entrants.contains(entrant))) {
+            Some(index) => self.standings.remove(index),
+            None => Standing { entrants: entrants.clone(), wins: [0, 0] },
+        };
+        standing.wins[usize::from(standing.entrants[1] == entrants[winner])] += 1;
+        if standing.winner().is_none() {
+            self.standings.push(standing.clone());
+        }
+        standing
+    }
+}
+
+/// A struct holding the rating of every profile and every computer difficulty.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Ladder {
+    profiles: vec::Vec<(String, i32)>,
+    computers: vec::Vec<(ai::Difficulty, i32)>,
+}
+
+impl Ladder {
+    pub fn profile_rating(&self, name: &str) -> i32 {
+        self.profiles.iter().find(|(profile, _)| profile == name).map_or(INITIAL_RATING, |(_, rating)| *rating)
+    }
+
+    pub fn computer_rating(&self, difficulty: ai::Difficulty) -> i32 {
+        self.computers.iter().find(|(other, _)| *other == difficulty).map_or(initial_computer_rating(difficulty), |(_, rating)| *rating)
+    }
+
+    /// Forget the rating of a profile.
+    pub fn remove_profile(&mut self, name: &str) {
+        self.profiles.retain(|(profile, _)| profile != name);
+    }
+
+    fn rating(&self, entrant: &Entrant) -> i32 {
+        match entrant {
+            Entrant::Profile(name) => self.profile_rating(name),
+            Entrant::Computer(difficulty) => self.computer_rating(*difficulty),
+        }
+    }
+
+    fn set_rating(&mut self, entrant: &Entrant, rating: i32) {
+        match entrant {
+            Entrant::Profile(name) => {
+                self.remove_profile(name);
+                self.profiles.push((name.clone(), rating));
+            },
+            Entrant::Computer(difficulty) => {
+                self.computers.retain(|(other, _)| other != difficulty);
+                self.computers.push((*difficulty, rating));
+            },
+        }
+    }
+
+    /// Update the ratings after a finished game, and after the series it belongs to if the game
+    /// decided it, and return how they changed along with how the series stands. Only games between
+    /// two opponents are rated: a profile against the computer, or two profiles taking turns on the
+    /// same computer. Humans are rated under the profile they play as, which has to be one of the given profiles.
+    pub fn record(&mut self, game: &game::Game, end: &game::GameEndReason, profiles: &[&str], series: &mut Series) -> (vec::Vec<Change>, Option<Standing>) {
+        let (game::GameEndReason::PlayerWon(winner), 2) = (end, game.get_players().len()) else {
+            return (vec![], None);
+        };
+        let humans: vec::Vec<usize> = game.human_seats();
+        let entrant = |seat: usize| -> Option<Entrant> {
+            match humans.contains(&seat) {
+                true => game.get_players()[seat].get_profile()
+                    .filter(|profile| profiles.contains(profile))
+                    .map(|profile| Entrant::Profile(profile.to_string())),
+                false => game.computer_difficulty().map(Entrant::Computer),
+            }
+        };
+        let (Some(first), Some(second)) = (entrant(0), entrant(1)) else {
+            return (vec![], None);
+        };
+        if first == second {
+            return (vec![], None);
+        }
+
+        let entrants: [Entrant; 2] = [first, second];
+        let (winner, loser): (usize, usize) = (*winner, 1 - *winner);
+        let before: [i32; 2] = [self.rating(&entrants[0]), self.rating(&entrants[1])];
+        let mut points: i32 = points_won(before[winner], before[loser]);
+
+        // winning the series is worked out from the ratings the game left the two opponents with
+        let standing: Standing = series.record(&entrants, winner);
+        if standing.winner().is_some() {
+            points += points_won(before[winner] + points, before[loser] - points);
+        }
+
+        let mut after: [i32; 2] = before;
+        after[winner] += points;
+        after[loser] -= points;
+        for (entrant, rating) in entrants.iter().zip(after) {
+            self.set_rating(entrant, rating);
+        }
+
+        let changes: vec::Vec<Change> = (0..2)
+            .map(|side| Change { name: entrants[side].to_string(), before: before[side], after: after[side] })
+            .collect();
+        (changes, Some(standing))
+    }
+}
@@ -0,0 +1,481 @@
+/// This module contains the renderer every screen draws through. Screens keep drawing whole frames
+/// with the usual terminal commands, which the renderer draws into a buffer instead of the terminal.
+/// When a frame is flushed, only the cells that changed since the last frame are sent to the
+/// terminal, so redrawing a frame that barely changed doesn't make the screen flicker.
+use std::{io::{self, Write}, vec};
+use crossterm::{cursor, queue, style, terminal};
+use unicode_width::UnicodeWidthChar;
+
+
+/// The escape character that starts every terminal command.
+const ESCAPE: u8 = 0x1b;
+
+/// The symbol of the cell covered by the second half of a wide character, like most CJK characters,
+/// which the terminal draws across two cells.
+const WIDE_TAIL: char = '\0';
+
+/// A struct holding how a cell is coloured and emphasised.
+#[derive(Clone, Copy, PartialEq)]
+struct Pen {
+    foreground: style::Color,
+    background: style::Color,
+    attributes: style::Attributes,
+}
+
+impl Default for Pen {
+    fn default() -> Self {
+        Self { foreground: style::Color::Reset, background: style::Color::Reset, attributes: style::Attributes::default() }
+    }
+}
+
+impl Pen {
+    /// Apply the parameters of a select graphic rendition command, such as "1" or "38;5;8".
+    fn apply(&mut self, parameters: &str) {
+        // underline styles are given as sub-parameters like "4:3", which only need the code before them
+        let codes: vec::Vec<u16> = parameters.split(';')
+            .map(|code| code.split(':').next().unwrap_or("").parse::<u16>().unwrap_or(0))
+            .collect();
+        let mut codes = codes.into_iter();
+        while let Some(code) = codes.next() {
+            match code {
+                0 => *self = Pen::default(),
+                1 => self.attributes.set(style::Attribute::Bold),
+                2 => self.attributes.set(style::Attribute::Dim),
+                3 => self.attributes.set(style::Attribute::Italic),
+                4 => self.attributes.set(style::Attribute::Underlined),
+                5 => self.attributes.set(style::Attribute::SlowBlink),
+                7 => self.attributes.set(style::Attribute::Reverse),
+                8 => self.attributes.set(style::Attribute::Hidden),
+                9 => self.attributes.set(style::Attribute::CrossedOut),
+                22 => {
+                    self.attributes.unset(style::Attribute::Bold);
+                    self.attributes.unset(style::Attribute::Dim);
+                },
+                23 => self.attributes.unset(style::Attribute::Italic),
+                24 => self.attributes.unset(style::Attribute::Underlined),
+                25 => self.attributes.unset(style::Attribute::SlowBlink),
+                27 => self.attributes.unset(style::Attribute::Reverse),
+                28 => self.attributes.unset(style::Attribute::Hidden),
+                29 => self.attributes.unset(style::Attribute::CrossedOut),
+                30..=37 => self.foreground = style::Color::AnsiValue((code - 30) as u8),
+                90..=97 => self.foreground = style::Color::AnsiValue((code - 90 + 8) as u8),
+                39 => self.foreground = style::Color::Reset,
+                40..=47 => self.background = style::Color::AnsiValue((code - 40) as u8),
+                100..=107 => self.background = style::Color::AnsiValue((code - 100 + 8) as u8),
+                49 => self.background = style::Color::Reset,
+                38 | 48 => {
+                    let color: style::Color = match codes.next() {
+                        Some(5) => style::Color::AnsiValue(codes.next().unwrap_or(0) as u8),
+                        Some(2) => style::Color::Rgb {
+                            r: codes.next().unwrap_or(0) as u8,
+                            g: codes.next().unwrap_or(0) as u8,
+                            b: codes.next().unwrap_or(0) as u8,
+                        },
+                        _ => style::Color::Reset,
+                    };
+                    if code == 38 { self.foreground = color } else { self.background = color }
+                },
+                _ => {}
+            }
+        }
+    }
+}
+
+/// A struct holding a single character on the screen and how it is drawn.
+#[derive(Clone, Copy, PartialEq)]
+struct Cell {
+    symbol: char,
+    pen: Pen,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self { symbol: ' ', pen: Pen::default() }
+    }
+}
+
+/// A struct holding every cell of the screen, row by row.
+#[derive(Clone)]
+struct Grid {
+    size: (u16, u16),
+    cells: vec::Vec<Cell>,
+}
+
+impl Grid {
+    /// Create an empty grid of the given size in columns and rows.
+    fn new(size: (u16, u16)) -> Self {
+        Self { size, cells: vec![Cell::default(); size.0 as usize * size.1 as usize] }
+    }
+
+    fn get(&self, column: u16, row: u16) -> &Cell {
+        &self.cells[row as usize * self.size.0 as usize + column as usize]
+    }
+
+    /// Empty every cell from the given position to the end of the row, or of the whole grid.
+    fn clear_from(&mut self, column: u16, row: u16, whole_grid: bool) {
+        let start: usize = (row as usize * self.size.0 as usize + column as usize).min(self.cells.len());
+        let end: usize = match whole_grid {
+            true => self.cells.len(),
+            false => ((row as usize + 1) * self.size.0 as usize).min(self.cells.len()),
+        };
+        self.cells[start..end].fill(Cell::default());
+    }
+}
+
+/// A struct that stands in for the terminal while a frame is drawn, and sends the changes to the
+/// real terminal when the frame is flushed.
+pub struct Renderer<W: Write = io::Stdout> {
+    out: W,
+    // the frame being drawn, and the frame the terminal shows, if it is known
+    back: Grid,
+    front: Option<Grid>,
+    // where the next character is drawn, and how
+    position: (u16, u16),
+    pen: Pen,
+    // the start of a command or character split between two writes
+    pending: vec::Vec<u8>,
+    // whether the grid follows the size of the terminal
+    follow_terminal: bool,
+    // whether frames are drawn at all, which they aren't once the alternate screen has been left
+    drawing: bool,
+}
+
+impl Renderer {
+    /// Create a renderer drawing to the standard output, sized to fit the terminal.
+    pub fn stdout() -> Self {
+        let mut renderer: Renderer = Renderer::new(io::stdout(), terminal::size().unwrap_or((80, 24)));
+        renderer.follow_terminal = true;
+        renderer
+    }
+}
+
+impl<W: Write> Renderer<W> {
+    /// Create a renderer drawing to the given output, for a terminal of the given size in columns and rows.
+    pub fn new(out: W, size: (u16, u16)) -> Self {
+        Self {
+            out,
+            back: Grid::new(size),
+            front: None,
+            position: (0, 0),
+            pen: Pen::default(),
+            pending: vec![],
+            follow_terminal: false,
+            drawing: true,
+        }
+    }
+
+    /// Get the output the renderer draws to.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.out
+    }
+
+    /// Draw a character at the current position, moving on to the next line at the edge of the
+    /// screen the same way the terminal would. Wide characters take up two cells, and are moved on to
+    /// the next line as a whole if only one cell is left. Characters without a width of their own,
+    /// like combining accents, can't be kept in a cell and are left out.
+    fn put(&mut self, symbol: char) {
+        let (width, height) = self.back.size;
+        let symbol_width: u16 = symbol.width().unwrap_or(0) as u16;
+        match symbol {
+            '\n' => self.position.1 = (self.position.1 + 1).min(height.saturating_sub(1)),
+            '\r' => self.position.0 = 0,
+            _ if symbol_width == 0 => {},
+            _ => {
+                if self.position.0 + symbol_width > width {
+                    self.position = (0, (self.position.1 + 1).min(height.saturating_sub(1)));
+                }
+                let (column, row) = self.position;
+                if column + symbol_width <= width && row < height {
+                    self.set(column, row, symbol);
+                    if symbol_width == 2 {
+                        self.set(column + 1, row, WIDE_TAIL);
+                    }
+                }
+                self.position.0 += symbol_width;
+            }
+        }
+    }
+
+    /// Set a cell of the frame being drawn. Drawing over either half of a wide character wipes out
+    /// the other half as well, the same way the terminal would.
+    fn set(&mut self, column: u16, row: u16, symbol: char) {
+        let width: usize = self.back.size.0 as usize;
+        let index: usize = row as usize * width + column as usize;
+        if symbol != WIDE_TAIL && self.back.cells[index].symbol == WIDE_TAIL && column > 0 {
+            self.back.cells[index - 1] = Cell { symbol: ' ', pen: self.back.cells[index - 1].pen };
+        }
+        if (column as usize) + 1 < width && self.back.cells[index + 1].symbol == WIDE_TAIL {
+            self.back.cells[index + 1] = Cell { symbol: ' ', pen: self.back.cells[index + 1].pen };
+        }
+        self.back.cells[index] = Cell { symbol, pen: self.pen };
+    }
+
+    /// Carry out a control sequence, given its parameters and the letter that ends it. Sequences that
+    /// don't draw anything, like the ones hiding the cursor or switching screens, are passed on to the
+    /// terminal straight away. Entering the alternate screen means the next frame is drawn in full,
+    /// while leaving it stops frames from being drawn over the user's own screen until it is entered again.
+    fn control(&mut self, parameters: &str, command: u8) -> io::Result<()> {
+        let numbers: vec::Vec<u16> = parameters.split(';').map(|number| number.parse::<u16>().unwrap_or(0)).collect();
+        let number = |i: usize, default: u16| numbers.get(i).copied().filter(|n| *n > 0).unwrap_or(default);
+        let (width, height) = self.back.size;
+        match command {
+            _ if parameters.starts_with('?') => {
+                write!(self.out, "\x1b[{}{}", parameters, command as char)?;
+                match (parameters, command) {
+                    ("?1049" | "?1047" | "?47", b'h') => {
+                        self.drawing = true;
+                        self.front = None;
+                    },
+                    ("?1049" | "?1047" | "?47", b'l') => {
+                        self.drawing = false;
+                        self.front = None;
+                        self.back = Grid::new(self.back.size);
+                    },
+                    _ => {},
+                }
+            },
+            b'H' | b'f' => self.position = (number(1, 1) - 1, number(0, 1) - 1),
+            b'A' => self.position.1 = self.position.1.saturating_sub(number(0, 1)),
+            b'B' => self.position.1 = (self.position.1 + number(0, 1)).min(height.saturating_sub(1)),
+            b'C' => self.position.0 = (self.position.0 + number(0, 1)).min(width.saturating_sub(1)),
+            b'D' => self.position.0 = self.position.0.saturating_sub(number(0, 1)),
+            b'G' => self.position.0 = number(0, 1) - 1,
+            b'J' => match numbers[0] {
+                // clearing the whole screen starts a new frame, which is drawn at the terminal's current size
+                2 | 3 => {
+                    if self.follow_terminal && let Ok(size) = terminal::size() && size != self.back.size {
+                        self.front = None;
+                    }
+                    let size: (u16, u16) = if self.follow_terminal { terminal::size().unwrap_or(self.back.size) } else { self.back.size };
+                    self.back = Grid::new(size);
+                },
+                _ => self.back.clear_from(self.position.0, self.position.1, true),
+            },
+            b'K' => match numbers[0] {
+                2 => self.back.clear_from(0, self.position.1, false),
+                _ => self.back.clear_from(self.position.0, self.position.1, false),
+            },
+            b'm' => self.pen.apply(parameters),
+            _ => {
+                write!(self.out, "\x1b[{}{}", parameters, command as char)?;
+                self.front = None;
+            },
+        }
+        Ok(())
+    }
+
+    /// Send the cells that changed since the last frame to the terminal. The whole frame is sent if
+    /// the terminal's contents aren't known, after clearing it.
+    fn draw_changes(&mut self) -> io::Result<()> {
+        let mut changes: vec::Vec<u8> = vec![];
+        let front: Grid = match self.front.take() {
+            Some(front) if front.size == self.back.size => front,
+            _ => {
+                queue!(changes, style::ResetColor, terminal::Clear(terminal::ClearType::All))?;
+                Grid::new(self.back.size)
+            },
+        };
+
+        // the terminal's cursor moves on by itself after every character, so runs of changed cells
+        // only need moving to once. the pen is only changed when the next cell is drawn differently
+        let mut position: Option<(u16, u16)> = None;
+        let mut pen: Pen = Pen::default();
+        for row in 0..self.back.size.1 {
+            for column in 0..self.back.size.0 {
+                let cell: &Cell = self.back.get(column, row);
+                // the second half of a wide character is drawn along with its first half
+                if cell == front.get(column, row) || cell.symbol == WIDE_TAIL {
+                    continue;
+                }
+                if position != Some((column, row)) {
+                    queue!(changes, cursor::MoveTo(column, row))?;
+                }
+                if cell.pen != pen {
+                    queue!(changes, style::ResetColor)?;
+                    if cell.pen.foreground != style::Color::Reset {
+                        queue!(changes, style::SetForegroundColor(cell.pen.foreground))?;
+                    }
+                    if cell.pen.background != style::Color::Reset {
+                        queue!(changes, style::SetBackgroundColor(cell.pen.background))?;
+                    }
+                    if !cell.pen.attributes.is_empty() {
+                        queue!(changes, style::SetAttributes(cell.pen.attributes))?;
+                    }
+                    pen = cell.pen;
+                }
+                write!(changes, "{}", cell.symbol)?;
+                position = Some((column + cell.symbol.width().unwrap_or(1) as u16, row));
+            }
+        }
+        if pen != Pen::default() {
+            queue!(changes, style::ResetColor)?;
+        }
+
+        self.out.write_all(&changes)?;
+        self.front = Some(self.back.clone());
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for Renderer<W> {
+    /// Draw text and carry out commands in the frame being drawn. Nothing reaches the terminal until
+    /// the frame is flushed, apart from commands that don't draw anything.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        let bytes: vec::Vec<u8> = std::mem::take(&mut self.pending);
+        let mut i: usize = 0;
+        while i < bytes.len() {
+            if bytes[i] == ESCAPE {
+                // control sequences start with ESC [ and end with a letter, while the rare escapes
+                // that aren't control sequences are passed on as they are
+                match bytes.get(i + 1) {
+                    None => break,
+                    Some(b'[') => match bytes[i + 2..].iter().position(|byte| (0x40..=0x7e).contains(byte)) {
+                        Some(end) => {
+                            let parameters: String = String::from_utf8_lossy(&bytes[i + 2..i + 2 + end]).to_string();
+                            self.control(&parameters, bytes[i + 2 + end])?;
+                            i += end + 3;
+                        },
+                        None => break,
+                    },
+                    Some(next) => {
+                        self.out.write_all(&[ESCAPE, *next])?;
+                        i += 2;
+                    },
+                }
+                continue;
+            }
+
+            // characters can take up to four bytes, which may not all have arrived yet
+            let length: usize = match bytes[i] {
+                0x00..=0x7f => 1,
+                0xc0..=0xdf => 2,
+                0xe0..=0xef => 3,
+                _ => 4,
+            };
+            if i + length > bytes.len() {
+                break;
+            }
+            match std::str::from_utf8(&bytes[i..i + length]) {
+                Ok(text) => text.chars().for_each(|symbol| self.put(symbol)),
+                Err(_) => self.put(char::REPLACEMENT_CHARACTER),
+            }
+            i += length;
+        }
+        self.pending = bytes[i..].to_vec();
+        Ok(buf.len())
+    }
+
+    /// Send the changes in the frame to the terminal, unless the alternate screen has been left.
+    fn flush(&mut self) -> io::Result<()> {
+        if self.drawing {
+            self.draw_changes()?;
+        }
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::execute;
+
+    /// Draw a frame of a board with the cursor on the given cell, the same way the game does.
+    fn draw_frame(renderer: &mut Renderer<vec::Vec<u8>>, cursor: (u16, u16)) -> io::Result<()> {
+        queue!(renderer, cursor::MoveTo(0, 0), terminal::Clear(terminal::ClearType::All), style::Print("TURN: 1"))?;
+        for row in 0..10 {
+            for column in 0..10 {
+                let cell: style::StyledContent<&str> = match (column, row) == cursor {
+                    true => style::Stylize::blue("◼"),
+                    false => style::Stylize::dark_grey("□"),
+                };
+                queue!(renderer, cursor::MoveTo(2 + column * 3, 5 + row), style::Print(cell))?;
+            }
+        }
+        renderer.flush()
+    }
+
+    #[test]
+    fn moving_the_cursor_only_redraws_two_cells() -> io::Result<()> {
+        let mut renderer: Renderer<vec::Vec<u8>> = Renderer::new(vec![], (80, 24));
+        draw_frame(&mut renderer, (0, 0))?;
+        let first_frame: usize = renderer.get_mut().len();
+        renderer.get_mut().clear();
+
+        draw_frame(&mut renderer, (1, 0))?;
+        let moved: usize = renderer.get_mut().len();
+        assert!(moved < 64, "moving the cursor sent {} bytes", moved);
+        assert!(moved * 10 < first_frame, "moving the cursor sent {} bytes, the first frame {}", moved, first_frame);
+        Ok(())
+    }
+
+    #[test]
+    fn an_unchanged_frame_sends_nothing() -> io::Result<()> {
+        let mut renderer: Renderer<vec::Vec<u8>> = Renderer::new(vec![], (80, 24));
+        draw_frame(&mut renderer, (3, 4))?;
+        renderer.get_mut().clear();
+
+        draw_frame(&mut renderer, (3, 4))?;
+        assert!(renderer.get_mut().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn leaving_the_screen_redraws_the_whole_frame() -> io::Result<()> {
+        let mut renderer: Renderer<vec::Vec<u8>> = Renderer::new(vec![], (80, 24));
+        draw_frame(&mut renderer, (0, 0))?;
+        let first_frame: usize = renderer.get_mut().len();
+        renderer.get_mut().clear();
+
+        execute!(renderer, terminal::LeaveAlternateScreen, terminal::EnterAlternateScreen)?;
+        draw_frame(&mut renderer, (0, 0))?;
+        assert!(renderer.get_mut().len() >= first_frame);
+        Ok(())
+    }
+
+    #[test]
+    fn nothing_is_drawn_after_leaving_the_screen() -> io::Result<()> {
+        let mut renderer: Renderer<vec::Vec<u8>> = Renderer::new(vec![], (80, 24));
+        draw_frame(&mut renderer, (0, 0))?;
+        renderer.get_mut().clear();
+
+        execute!(renderer, terminal::LeaveAlternateScreen)?;
+        assert_eq!(renderer.get_mut().as_slice(), b"\x1b[?1049l");
+        draw_frame(&mut renderer, (1, 0))?;
+        assert_eq!(renderer.get_mut().as_slice(), b"\x1b[?1049l");
+        Ok(())
+    }
+
+    #[test]
+    fn wide_characters_take_up_two_cells() -> io::Result<()> {
+        let mut renderer: Renderer<vec::Vec<u8>> = Renderer::new(vec![], (8, 2));
+        queue!(renderer, cursor::MoveTo(0, 0), style::Print("名前ab"), cursor::MoveTo(6, 1), style::Print("a名"))?;
+        renderer.flush()?;
+        let row: String = (0..8).map(|column| renderer.back.get(column, 0).symbol).collect();
+        assert_eq!(row, "名\0前\0ab  ");
+        // the wide character doesn't fit after the a, so it goes on the next line, which is the last
+        let row: String = (0..8).map(|column| renderer.back.get(column, 1).symbol).collect();
+        assert_eq!(row, "名\0    a ");
+        assert!(!renderer.get_mut().contains(&0));
+        renderer.get_mut().clear();
+
+        // drawing over half of a wide character wipes out all of it
+        queue!(renderer, cursor::MoveTo(1, 0), style::Print("x"))?;
+        renderer.flush()?;
+        let row: String = (0..8).map(|column| renderer.back.get(column, 0).symbol).collect();
+        assert_eq!(row, " x前\0ab  ");
+        Ok(())
+    }
+
+    #[test]
+    fn hiding_the_cursor_keeps_the_frame() -> io::Result<()> {
+        let mut renderer: Renderer<vec::Vec<u8>> = Renderer::new(vec![], (80, 24));
+        draw_frame(&mut renderer, (0, 0))?;
+        renderer.get_mut().clear();
+
+        execute!(renderer, cursor::Hide, cursor::Show)?;
+        draw_frame(&mut renderer, (0, 0))?;
+        assert_eq!(renderer.get_mut().as_slice(), b"\x1b[?25l\x1b[?25h");
+        Ok(())
+    }
+}
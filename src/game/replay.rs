@@ -0,0 +1,189 @@
+/// This module contains logic for recording games and playing them back.
+use std::{time, vec};
+use serde::{Deserialize, Serialize};
+use crate::game::components::{arsenal, player, ship};
+use crate::game::rules;
+
+
+/// An enum defining everything that can happen in a recorded game.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub enum Event {
+    // the player in the given seat placed a ship on the given cells
+    Place {
+        seat: usize,
+        ship_type: ship::ShipType,
+        cells: vec::Vec<(usize, usize)>,
+        orientation: ship::ShipOrientation,
+        mirrored: bool,
+    },
+    // the player in the given seat fired a shot at the player in the target seat
+    Fire { seat: usize, target: usize, shot: arsenal::Shot },
+    // the player in the given seat moved one of their ships, given by its index
+    Move { seat: usize, index: usize, ship_move: ship::ShipMove },
+}
+
+/// A struct holding a single event along with when it happened.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    // milliseconds since the unix epoch
+    pub time: u64,
+    pub event: Event,
+}
+
+/// A struct holding everything needed to play a game back from the start.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Replay {
+    rules: rules::GameRules,
+    names: vec::Vec<String>,
+    entries: vec::Vec<Entry>,
+}
+
+/// Get the current time in milliseconds since the unix epoch.
+pub fn now() -> u64 {
+    time::SystemTime::now()
+        .duration_since(time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+/// Format a time in milliseconds since the unix epoch as a UTC date and time, like "2024-05-17 18:03".
+pub fn format_time(millis: u64) -> String {
+    let secs: u64 = millis / 1000;
+    let (days, rest) = ((secs / 86400) as i64, secs % 86400);
+
+    // convert days since the epoch to a civil date
+    let z: i64 = days + 719468;
+    let era: i64 = z.div_euclid(146097);
+    let doe: i64 = z - era * 146097;
+    let yoe: i64 = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy: i64 = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp: i64 = (5 * doy + 2) / 153;
+    let day: i64 = doy - (153 * mp + 2) / 5 + 1;
+    let month: i64 = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year: i64 = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02} {:02}:{:02}", year, month, day, rest / 3600, rest % 3600 / 60)
+}
+
+/// Format a time in milliseconds since the unix epoch as a UTC date and time down to the
+/// millisecond, like "2024-05-17 18:03:07.412", so it can be read back exactly.
+pub fn format_exact_time(millis: u64) -> String {
+    format!("{}:{:02}.{:03}", format_time(millis), millis / 1000 % 60, millis % 1000)
+}
+
+/// Parse a UTC date and time in the format written by `format_time` or `format_exact_time` back
+/// into milliseconds since the unix epoch. Returns None if the text is not a valid date and time.
+pub fn parse_time(text: &str) -> Option<u64> {
+    let (date, clock) = text.trim().split_once(' ')?;
+    let mut date_parts = date.split('-').map(|part| part.parse::<i64>().ok());
+    let (year, month, day) = (date_parts.next()??, date_parts.next()??, date_parts.next()??);
+    let mut clock_parts = clock.split(':');
+    let (hours, minutes) = (clock_parts.next()?.parse::<u64>().ok()?, clock_parts.next()?.parse::<u64>().ok()?);
+
+    // the seconds and milliseconds can be left out, as `format_time` does
+    let (seconds, millis) = match clock_parts.next() {
+        Some(seconds) => match seconds.split_once('.') {
+            Some((seconds, millis)) if millis.len() == 3 => (seconds.parse::<u64>().ok()?, millis.parse::<u64>().ok()?),
+            Some(_) => return None,
+            None => (seconds.parse::<u64>().ok()?, 0),
+        },
+        None => (0, 0),
+    };
+    if clock_parts.next().is_some() || !(1..=12).contains(&month) || !(1..=31).contains(&day) || hours > 23 || minutes > 59 || seconds > 59 {
+        return None;
+    }
+
+    // convert the civil date to days since the epoch
+    let year: i64 = if month <= 2 { year - 1 } else { year };
+    let era: i64 = year.div_euclid(400);
+    let yoe: i64 = year - era * 400;
+    let doy: i64 = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let doe: i64 = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days: u64 = u64::try_from(era * 146097 + doe - 719468).ok()?;
+
+    Some((days * 86400 + hours * 3600 + minutes * 60 + seconds) * 1000 + millis)
+}
+
+impl Replay {
+    /// Start recording a game between the given players, with the ships they have placed.
+    pub fn new(rules: rules::GameRules, players: &[player::Player]) -> Self {
+        let mut replay: Self = Self {
+            rules,
+            names: players.iter().map(|player| player.get_name().to_string()).collect(),
+            entries: vec![],
+        };
+        // every ship has been placed by the time the recording starts
+        let time: u64 = now();
+        for (seat, player) in players.iter().enumerate() {
+            for ship in player.get_ships() {
+                replay.entries.push(Entry {
+                    time,
+                    event: Event::Place {
+                        seat,
+                        ship_type: ship.get_type(),
+                        cells: ship.get_cells().to_vec(),
+                        orientation: ship.orientation(),
+                        mirrored: ship.is_mirrored(),
+                    },
+                });
+            }
+        }
+        replay
+    }
+
+    /// Rebuild a replay from the events of a game and when they happened.
+    pub fn from_entries(rules: rules::GameRules, names: vec::Vec<String>, entries: vec::Vec<Entry>) -> Self {
+        Self { rules, names, entries }
+    }
+
+    pub fn get_rules(&self) -> &rules::GameRules {
+        &self.rules
+    }
+
+    pub fn get_names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn get_entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Record an event as happening now.
+    pub fn push(&mut self, event: Event) {
+        self.entries.push(Entry { time: now(), event });
+    }
+
+    /// Get the time the recording was started.
+    pub fn started(&self) -> u64 {
+        self.entries.first().map_or(0, |entry| entry.time)
+    }
+
+    /// Describe the replay in a single line, for listing it in a menu.
+    pub fn summary(&self) -> String {
+        format!("{}  {:<14} {}", format_time(self.started()), self.rules.mode.to_string(), self.names.join(" vs "))
+    }
+
+    /// Rebuild the players as they were after the given number of events, with a description of
+    /// the last event. Ships are placed on fresh boards and every shot and move is applied again.
+    pub fn play(&self, steps: usize) -> (vec::Vec<player::Player>, Option<String>) {
+        let mut players: vec::Vec<player::Player> = self.names.iter().map(|name| player::Player::new(name)).collect();
+        let mut description: Option<String> = None;
+
+        for entry in self.entries.iter().take(steps) {
+            description = Some(match &entry.event {
+                Event::Place { seat, ship_type, cells, orientation, mirrored } => {
+                    players[*seat].add_ship(cells.clone(), *ship_type, *orientation, *mirrored);
+                    format!("{}: placed {}", self.names[*seat], ship_type)
+                },
+                Event::Fire { seat, target, shot } => {
+                    let outcome: arsenal::Outcome = players[*target].take_shot(shot);
+                    format!("{} -> {}: {} - {}", self.names[*seat], self.names[*target], shot, outcome)
+                },
+                Event::Move { seat, index, ship_move } => {
+                    players[*seat].move_ship(*index, *ship_move);
+                    format!("{}: moved a ship", self.names[*seat])
+                },
+            });
+        }
+        (players, description)
+    }
+}
@@ -0,0 +1,95 @@
+/// This module contains the rule sets a game can be played under.
+use std::{fmt, vec};
+use serde::{Deserialize, Serialize};
+use crate::game::components::{ai, arsenal, ship};
+
+
+/// An enum defining every game mode that can be played.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum GameMode {
+    Classic,
+    Arsenal,
+    MobileFleet,
+    TetrisFleet,
+}
+
+impl fmt::Display for GameMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameMode::Classic => write!(f, "Classic"),
+            GameMode::Arsenal => write!(f, "Arsenal"),
+            GameMode::MobileFleet => write!(f, "Mobile Fleet"),
+            GameMode::TetrisFleet => write!(f, "Tetris Fleet")
+        }
+    }
+}
+
+impl GameMode {
+    /// A static array containing all possible game modes to iterate over.
+    pub const ALL: [GameMode; 4] = [
+        GameMode::Classic,
+        GameMode::Arsenal,
+        GameMode::MobileFleet,
+        GameMode::TetrisFleet
+    ];
+
+    /// Generate a consuming iterator over the game modes
+    pub fn iter() -> impl Iterator<Item = GameMode> {
+        Self::ALL.into_iter()
+    }
+
+    /// Get a short description of the mode, shown next to it in the menu.
+    pub fn description(&self) -> &'static str {
+        match self {
+            GameMode::Classic => "One shot per turn, first to sink the enemy fleet wins",
+            GameMode::Arsenal => "Adds limited bombs, torpedoes, sonar pings and a decoy ship",
+            GameMode::MobileFleet => "Instead of firing, move or turn one undamaged ship",
+            GameMode::TetrisFleet => "Ships come in L, T, plus and block shapes that can be turned and flipped",
+        }
+    }
+}
+
+/// A struct that holds every setting that changes how a game is played.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct GameRules {
+    pub mode: GameMode,
+    pub difficulty: ai::Difficulty,
+    // whether the seats are split into two teams
+    pub teams: bool,
+}
+
+impl GameRules {
+    /// Create a new rule set for the given mode and computer difficulty.
+    pub fn new(mode: GameMode, difficulty: ai::Difficulty) -> Self {
+        Self { mode, difficulty, teams: false }
+    }
+
+    /// Get the team of the player in the given seat. In team play, seats alternate between
+    /// the two teams so that teammates take their turns one after the other. Otherwise,
+    /// every player is on a team of their own.
+    pub fn team_of(&self, seat: usize) -> usize {
+        if self.teams { seat % 2 } else { seat }
+    }
+
+    /// Get the ships each player has to place at the start of the game.
+    pub fn fleet(&self) -> vec::Vec<ship::ShipType> {
+        match self.mode {
+            GameMode::Arsenal => [ship::ShipType::ALL.as_slice(), &[ship::ShipType::DECOY]].concat(),
+            GameMode::TetrisFleet => ship::ShipType::TETRIS.to_vec(),
+            GameMode::Classic | GameMode::MobileFleet => ship::ShipType::ALL.to_vec(),
+        }
+    }
+
+    /// Get the special weapons each player starts the game with.
+    pub fn arsenal(&self) -> arsenal::Arsenal {
+        match self.mode {
+            GameMode::Arsenal => arsenal::Arsenal::standard(),
+            GameMode::Classic | GameMode::MobileFleet | GameMode::TetrisFleet => arsenal::Arsenal::empty(),
+        }
+    }
+
+    /// Returns true if players may move their ships instead of firing.
+    pub fn ships_can_move(&self) -> bool {
+        self.mode == GameMode::MobileFleet
+    }
+}
@@ -0,0 +1,257 @@
+/// This module contains the settings the player can change, which are kept in a TOML file
+/// in the user's config directory between runs.
+use std::{collections::BTreeMap, fmt, time, vec};
+use serde::{Deserialize, Serialize};
+use crate::game::components::ai;
+use crate::game::{input, rules, themes};
+
+/// An enum defining how fast shots are animated, if at all.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnimationSpeed {
+    Off,
+    Slow,
+    Normal,
+    Fast,
+}
+
+impl fmt::Display for AnimationSpeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnimationSpeed::Off => write!(f, "Off"),
+            AnimationSpeed::Slow => write!(f, "Slow"),
+            AnimationSpeed::Normal => write!(f, "Normal"),
+            AnimationSpeed::Fast => write!(f, "Fast")
+        }
+    }
+}
+
+impl AnimationSpeed {
+    /// A static array containing every animation speed, from none to fastest.
+    pub const ALL: [AnimationSpeed; 4] = [
+        AnimationSpeed::Off,
+        AnimationSpeed::Slow,
+        AnimationSpeed::Normal,
+        AnimationSpeed::Fast
+    ];
+
+    /// Get how long each frame of an animation is shown for, or None if animations are turned off.
+    pub fn tick(&self) -> Option<time::Duration> {
+        match self {
+            AnimationSpeed::Off => None,
+            AnimationSpeed::Slow => Some(time::Duration::from_millis(140)),
+            AnimationSpeed::Normal => Some(time::Duration::from_millis(90)),
+            AnimationSpeed::Fast => Some(time::Duration::from_millis(45)),
+        }
+    }
+}
+
+/// An enum defining which characters the game is drawn with.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Charset {
+    Auto,
+    Unicode,
+    Ascii,
+}
+
+impl fmt::Display for Charset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Charset::Auto => write!(f, "Automatic"),
+            Charset::Unicode => write!(f, "Unicode"),
+            Charset::Ascii => write!(f, "ASCII only")
+        }
+    }
+}
+
+impl Charset {
+    /// A static array containing every character set.
+    pub const ALL: [Charset; 3] = [
+        Charset::Auto,
+        Charset::Unicode,
+        Charset::Ascii
+    ];
+
+    /// Returns true if only ASCII characters should be drawn. Picking automatically goes by the
+    /// locale, which names UTF-8 when the terminal can show the usual glyphs.
+    pub fn is_ascii(&self) -> bool {
+        match self {
+            Charset::Auto => !locale_is_utf8(),
+            Charset::Unicode => false,
+            Charset::Ascii => true,
+        }
+    }
+}
+
+/// Returns true if the locale the game was started in uses UTF-8.
+fn locale_is_utf8() -> bool {
+    // the first of these that is set decides the character set, as it does for the C library
+    let locale: Option<String> = ["LC_ALL", "LC_CTYPE", "LANG"].iter()
+        .find_map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()));
+    match locale {
+        Some(locale) => {
+            let locale: String = locale.to_ascii_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        },
+        // windows doesn't set a locale, and its terminals show unicode anyway
+        None => cfg!(windows),
+    }
+}
+
+/// An enum defining the built-in sets of keys used to play the game.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyPreset {
+    Arrows,
+    Vim,
+    Wasd,
+}
+
+impl fmt::Display for KeyPreset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyPreset::Arrows => write!(f, "Arrow keys"),
+            KeyPreset::Vim => write!(f, "Vim (hjkl)"),
+            KeyPreset::Wasd => write!(f, "WASD")
+        }
+    }
+}
+
+impl KeyPreset {
+    /// A static array containing every key preset.
+    pub const ALL: [KeyPreset; 3] = [
+        KeyPreset::Arrows,
+        KeyPreset::Vim,
+        KeyPreset::Wasd
+    ];
+}
+
+/// A struct holding the rules new games are set up with by default.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RuleSettings {
+    pub mode: rules::GameMode,
+    pub difficulty: ai::Difficulty,
+}
+
+impl Default for RuleSettings {
+    fn default() -> Self {
+        Self { mode: rules::GameMode::Classic, difficulty: ai::Difficulty::Normal }
+    }
+}
+
+/// A struct holding how the game is drawn.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DisplaySettings {
+    pub theme: themes::Theme,
+    pub charset: Charset,
+    pub animation_speed: AnimationSpeed,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        Self { theme: themes::Theme::Classic, charset: Charset::Auto, animation_speed: AnimationSpeed::Normal }
+    }
+}
+
+/// A struct holding the keys the game is played with.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeySettings {
+    pub preset: KeyPreset,
+    // the keys replacing the preset's for a command, separated by spaces
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub bindings: BTreeMap<input::Command, String>,
+}
+
+impl Default for KeySettings {
+    fn default() -> Self {
+        Self { preset: KeyPreset::Arrows, bindings: BTreeMap::new() }
+    }
+}
+
+/// A struct holding how the mouse is used.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MouseSettings {
+    // aim with the first click on a cell, and only fire with a second click on the same cell
+    pub confirm_clicks: bool,
+}
+
+/// A struct holding where network games are hosted and joined by default.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkSettings {
+    pub host: String,
+    pub port: u16,
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        Self { host: "127.0.0.1".to_string(), port: 7878 }
+    }
+}
+
+/// A struct holding every setting. Sections and values missing from the file keep their defaults.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub rules: RuleSettings,
+    pub display: DisplaySettings,
+    pub keys: KeySettings,
+    pub mouse: MouseSettings,
+    pub network: NetworkSettings,
+}
+
+impl Settings {
+    /// Check the values that can't be checked while reading the file, and describe every problem found.
+    pub fn validate(&self) -> vec::Vec<String> {
+        let mut problems: vec::Vec<String> = vec![];
+        for (command, keys) in self.keys.bindings.iter() {
+            if keys.split_whitespace().next().is_none() {
+                problems.push(format!("keys.bindings.{}: no keys given", command));
+            }
+            for key in keys.split_whitespace().filter(|key| input::parse_key(key).is_none()) {
+                problems.push(format!("keys.bindings.{}: '{}' is not a key", command, key));
+            }
+        }
+        for conflict in input::KeyMap::new(&self.keys).conflicts() {
+            problems.push(format!("keys: {}", conflict));
+        }
+        if !is_valid_host(&self.network.host) {
+            problems.push(format!("network.host: '{}' is not a host name or address", self.network.host));
+        }
+        if self.network.port == 0 {
+            problems.push("network.port: the port can't be 0".to_string());
+        }
+        problems
+    }
+}
+
+/// Returns true if the text could be a host name or address.
+pub fn is_valid_host(host: &str) -> bool {
+    !host.is_empty() && host.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == ':')
+}
+
+/// Get the comment written at the top of the settings file, explaining the values it can hold.
+pub fn file_header() -> String {
+    [
+        "# Settings for Battleship. Values left out keep their defaults.".to_string(),
+        "#".to_string(),
+        "# rules.mode               Classic, Arsenal, MobileFleet, TetrisFleet".to_string(),
+        "# rules.difficulty         Easy, Normal, Hard".to_string(),
+        format!("# display.theme            {}", themes::Theme::iter().map(|theme| theme.name()).collect::<vec::Vec<&str>>().join(", ")),
+        "# display.charset          auto, unicode, ascii (auto uses ASCII unless the locale is UTF-8)".to_string(),
+        "# display.animation_speed  off, slow, normal, fast".to_string(),
+        "# keys.preset              arrows, vim, wasd".to_string(),
+        "# keys.bindings.<command>  keys replacing the preset's, separated by spaces, e.g. rotate = \"e space\"".to_string(),
+        format!("#                          commands: {}", input::Command::iter().map(|command| command.to_string()).collect::<vec::Vec<String>>().join(", ")),
+        "# mouse.confirm_clicks     true to fire only when the cell aimed at is clicked again".to_string(),
+        "# network.host             the host name or address to join games at".to_string(),
+        "# network.port             the port to host and join games on".to_string(),
+        String::new(),
+        String::new(),
+    ].join("\n")
+}
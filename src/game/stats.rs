@@ -0,0 +1,137 @@
+/// This module contains logic for keeping track of the player's results across games.
+use std::vec;
+use serde::{Deserialize, Serialize};
+use crate::game::components::ai;
+use crate::game::{game, rules};
+
+
+/// A struct counting the shots a single player fired during a game.
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Tally {
+    // the number of turns spent firing
+    pub shots: usize,
+    // the number of cells struck, and how many of them were ships
+    pub cells: usize,
+    pub hits: usize,
+}
+
+impl Tally {
+    /// Get the share of struck cells that were ships, from 0 to 1, if any shots have been fired.
+    pub fn accuracy(&self) -> Option<f64> {
+        match self.cells {
+            0 => None,
+            cells => Some(self.hits as f64 / cells as f64),
+        }
+    }
+}
+
+/// A struct holding the wins and losses for one game mode against one computer difficulty.
+/// The difficulty is None for games without any computer players.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Record {
+    pub mode: rules::GameMode,
+    pub difficulty: Option<ai::Difficulty>,
+    pub wins: usize,
+    pub losses: usize,
+}
+
+/// A struct holding the player's results across every game they have finished.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Statistics {
+    records: vec::Vec<Record>,
+    // every shot fired, across all games
+    shots: Tally,
+    // the total shots fired in won games, to average them over the wins
+    shots_to_win: usize,
+    current_streak: usize,
+    longest_streak: usize,
+    // the fewest turns a game has been won in
+    fastest_win: Option<usize>,
+}
+
+impl Statistics {
+    pub fn get_records(&self) -> &[Record] {
+        &self.records
+    }
+
+    pub fn games_played(&self) -> usize {
+        self.wins() + self.losses()
+    }
+
+    pub fn wins(&self) -> usize {
+        self.records.iter().map(|record| record.wins).sum()
+    }
+
+    pub fn losses(&self) -> usize {
+        self.records.iter().map(|record| record.losses).sum()
+    }
+
+    /// Get the share of struck cells that were ships, from 0 to 1, if any shots have been fired.
+    pub fn accuracy(&self) -> Option<f64> {
+        self.shots.accuracy()
+    }
+
+    /// Get the average number of shots fired in a won game, if any games have been won.
+    pub fn average_shots_to_win(&self) -> Option<f64> {
+        match self.wins() {
+            0 => None,
+            wins => Some(self.shots_to_win as f64 / wins as f64),
+        }
+    }
+
+    pub fn current_streak(&self) -> usize {
+        self.current_streak
+    }
+
+    pub fn longest_streak(&self) -> usize {
+        self.longest_streak
+    }
+
+    pub fn fastest_win(&self) -> Option<usize> {
+        self.fastest_win
+    }
+
+    /// Forget every result.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Record the result of a finished game. Only games with a single human player are recorded,
+    /// since the statistics describe that player's results against the computer.
+    pub fn record(&mut self, game: &game::Game, end: &game::GameEndReason) {
+        let humans: vec::Vec<usize> = game.human_seats();
+        let [seat] = humans.as_slice() else { return };
+        let won: bool = match end {
+            game::GameEndReason::PlayerWon(winner) => winner == seat,
+            game::GameEndReason::TeamWon(team) => game.get_rules().team_of(*seat) == *team,
+            game::GameEndReason::Saved => return,
+        };
+
+        // find the record for this mode and the computers' difficulty, or start a new one
+        let mode: rules::GameMode = game.get_rules().mode;
+        let difficulty: Option<ai::Difficulty> = game.computer_difficulty();
+        let index: usize = match self.records.iter().position(|r| r.mode == mode && r.difficulty == difficulty) {
+            Some(index) => index,
+            None => {
+                self.records.push(Record { mode, difficulty, wins: 0, losses: 0 });
+                self.records.len() - 1
+            }
+        };
+
+        let tally: Tally = game.get_tallies()[*seat];
+        self.shots.shots += tally.shots;
+        self.shots.cells += tally.cells;
+        self.shots.hits += tally.hits;
+
+        if won {
+            self.records[index].wins += 1;
+            self.shots_to_win += tally.shots;
+            self.current_streak += 1;
+            self.longest_streak = self.longest_streak.max(self.current_streak);
+            self.fastest_win = Some(self.fastest_win.map_or(game.get_turn_count(), |fastest| fastest.min(game.get_turn_count())));
+        } else {
+            self.records[index].losses += 1;
+            self.current_streak = 0;
+        }
+    }
+}
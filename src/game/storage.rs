@@ -0,0 +1,433 @@
+/// This module contains logic for keeping game data on disk between runs.
+use std::{fs, io, path};
+use serde::{Serialize, de::DeserializeOwned};
+use crate::game::{achievements, formations, game, notation, rating, replay, settings, stats};
+
+
+/// The name of the directory, inside the user's home directory, that game data is kept in.
+const DATA_DIR: &str = ".battleship";
+
+/// The name of the file an in-progress game is saved to.
+const SAVE_FILE: &str = "save.bin";
+
+/// The bytes a saved game starts with, followed by the version of the save format. The version goes
+/// up whenever the game changes what it saves, so a save written by another version is recognised
+/// instead of being misread.
+const SAVE_MAGIC: &[u8; 4] = b"BSAV";
+const SAVE_VERSION: u16 = 2;
+
+/// The bytes a saved replay starts with, followed by the version of the replay format, which goes
+/// up whenever the game changes what a replay records.
+const REPLAY_MAGIC: &[u8; 4] = b"BRPL";
+const REPLAY_VERSION: u16 = 1;
+
+/// The name of the file a profile's statistics are kept in.
+const STATS_FILE: &str = "stats.bin";
+
+/// The name of the file the achievements a profile has unlocked are kept in.
+const ACHIEVEMENTS_FILE: &str = "achievements.bin";
+
+/// The name of the file the fleet formations a profile has saved are kept in.
+const FORMATIONS_FILE: &str = "formations.bin";
+
+/// The name of the directory, inside the data directory, that profiles are kept in. Every profile
+/// gets a directory of its own, named after the profile, holding everything that belongs to it.
+const PROFILE_DIR: &str = "profiles";
+
+/// The name of the file holding the name of the profile that was last played with.
+const ACTIVE_PROFILE_FILE: &str = "profile.txt";
+
+/// The name of the file the ratings of every profile and computer difficulty are kept in.
+const RATINGS_FILE: &str = "ratings.bin";
+
+/// The name of the file the series being played between every pair of opponents are kept in.
+const SERIES_FILE: &str = "series.bin";
+
+/// The name of the directory, inside the user's config directory, that the settings file is kept in.
+const CONFIG_DIR: &str = "battleship";
+
+/// The name of the settings file.
+const SETTINGS_FILE: &str = "settings.toml";
+
+/// The most characters a profile name can have.
+pub const MAX_PROFILE_NAME: usize = 16;
+
+/// The name of the directory, inside the data directory, that replays are kept in.
+const REPLAY_DIR: &str = "replays";
+
+/// The name of the directory, inside the data directory, that games written in the notation are exported to.
+const NOTATION_DIR: &str = "notation";
+
+/// An error returned when game data could not be read or written.
+#[derive(Debug, thiserror::Error)]
+pub enum StorageError {
+    #[error("could not access game data: {0}")]
+    Io(#[from] io::Error),
+    #[error("game data is corrupted: {0}")]
+    Encoding(#[from] bincode::Error),
+    #[error("the saved game was made by a different version of the game")]
+    IncompatibleSave,
+    #[error("the replay was made by a different version of the game")]
+    IncompatibleReplay,
+    #[error("{0}")]
+    Settings(String),
+}
+
+/// Get the directory game data is kept in, creating it if it doesn't exist yet. Falls
+/// back to the working directory if the home directory can't be found.
+pub fn data_dir() -> io::Result<path::PathBuf> {
+    let home: path::PathBuf = std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(path::PathBuf::from)
+        .unwrap_or_default();
+    let dir: path::PathBuf = home.join(DATA_DIR);
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Get the path of the global settings file. It is kept in the user's config directory, which is
+/// $XDG_CONFIG_HOME or ~/.config on Unix and %APPDATA% on Windows.
+pub fn settings_path() -> path::PathBuf {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .or_else(|| std::env::var_os("APPDATA"))
+        .map(path::PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| path::PathBuf::from(home).join(".config")))
+        .unwrap_or_default()
+        .join(CONFIG_DIR)
+        .join(SETTINGS_FILE)
+}
+
+/// Get the path of the settings file of the active profile, or of the global settings file if no
+/// profile is active. Every profile keeps its own settings, laid over the global ones.
+pub fn active_settings_path() -> Result<path::PathBuf, StorageError> {
+    match active_profile() {
+        Some(profile) => Ok(data_dir()?.join(profile_file(&profile, SETTINGS_FILE))),
+        None => Ok(settings_path()),
+    }
+}
+
+/// Read the settings written in a file, or no settings at all if there is no such file.
+fn read_settings(path: &path::Path) -> Result<toml::Table, StorageError> {
+    if !path.is_file() {
+        return Ok(toml::Table::new());
+    }
+    let text: String = fs::read_to_string(path)?;
+    toml::from_str(&text).map_err(|e| StorageError::Settings(format!("{}: {}", path.display(), e)))
+}
+
+/// Lay the given settings over the ones underneath, replacing any value they both have.
+fn merge_settings(under: &mut toml::Table, over: toml::Table) {
+    for (key, value) in over {
+        match (under.get_mut(&key), value) {
+            (Some(toml::Value::Table(under)), toml::Value::Table(over)) => merge_settings(under, over),
+            (_, value) => {
+                under.insert(key, value);
+            },
+        }
+    }
+}
+
+/// Load the settings of the active profile, laid over the global ones. Settings missing from both
+/// files keep their defaults, while a file that can't be read or holds values that don't make sense
+/// is reported as an error.
+pub fn load_settings() -> Result<settings::Settings, StorageError> {
+    let mut table: toml::Table = read_settings(&settings_path())?;
+    if active_profile().is_some() {
+        merge_settings(&mut table, read_settings(&active_settings_path()?)?);
+    }
+
+    let loaded: settings::Settings = toml::Value::Table(table).try_into().map_err(|e: toml::de::Error| StorageError::Settings(e.to_string()))?;
+    let problems: Vec<String> = loaded.validate();
+    if !problems.is_empty() {
+        return Err(StorageError::Settings(problems.join("\n")));
+    }
+    Ok(loaded)
+}
+
+/// Save the settings of the active profile, replacing its settings file.
+pub fn save_settings(settings: &settings::Settings) -> Result<(), StorageError> {
+    let path: path::PathBuf = active_settings_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let text: String = toml::to_string_pretty(settings).map_err(|e| StorageError::Settings(e.to_string()))?;
+    fs::write(path, format!("{}{}", settings::file_header(), text))?;
+    Ok(())
+}
+
+/// Write a value to the given file in the data directory, replacing what was there before.
+fn write<T: Serialize>(file: &str, value: &T) -> Result<(), StorageError> {
+    let bytes: Vec<u8> = bincode::serialize(value)?;
+    fs::write(data_dir()?.join(file), bytes)?;
+    Ok(())
+}
+
+/// Read a value from the given file in the data directory.
+fn read<T: DeserializeOwned>(file: &str) -> Result<T, StorageError> {
+    let bytes: Vec<u8> = fs::read(data_dir()?.join(file))?;
+    Ok(bincode::deserialize(&bytes)?)
+}
+
+/// Returns true if there is a saved game to continue.
+pub fn has_saved_game() -> bool {
+    data_dir().is_ok_and(|dir| dir.join(SAVE_FILE).is_file())
+}
+
+/// Serialize a value behind the given magic bytes and format version.
+fn versioned<T: Serialize>(magic: &[u8; 4], version: u16, value: &T) -> Result<Vec<u8>, StorageError> {
+    let mut bytes: Vec<u8> = magic.to_vec();
+    bytes.extend_from_slice(&version.to_le_bytes());
+    bytes.extend(bincode::serialize(value)?);
+    Ok(bytes)
+}
+
+/// Get the serialized value that follows the given magic bytes and format version, or None if the
+/// bytes were written in another version of the format, or before the format had a version.
+fn unversioned<'a>(bytes: &'a [u8], magic: &[u8; 4], version: u16) -> Option<&'a [u8]> {
+    let header: usize = magic.len() + 2;
+    if bytes.len() < header || bytes[..magic.len()] != magic[..] || bytes[magic.len()..header] != version.to_le_bytes() {
+        return None;
+    }
+    Some(&bytes[header..])
+}
+
+/// Save the game along with the version of the save format, replacing any game that was saved before.
+pub fn save_game(game: &game::Game) -> Result<(), StorageError> {
+    fs::write(data_dir()?.join(SAVE_FILE), versioned(SAVE_MAGIC, SAVE_VERSION, game)?)?;
+    Ok(())
+}
+
+/// Load the saved game. Saves written in another version of the save format, including the ones from
+/// before saves had a version, can't be loaded.
+pub fn load_game() -> Result<game::Game, StorageError> {
+    let bytes: Vec<u8> = fs::read(data_dir()?.join(SAVE_FILE))?;
+    let game: &[u8] = unversioned(&bytes, SAVE_MAGIC, SAVE_VERSION).ok_or(StorageError::IncompatibleSave)?;
+    Ok(bincode::deserialize(game)?)
+}
+
+/// Remove the saved game, if there is one.
+pub fn delete_saved_game() -> Result<(), StorageError> {
+    let path: path::PathBuf = data_dir()?.join(SAVE_FILE);
+    if path.is_file() {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+/// Returns true if the name can be used for a profile. Since profiles are kept in directories named
+/// after them, names are limited to letters, digits, spaces, dashes and underscores.
+pub fn is_valid_profile_name(name: &str) -> bool {
+    name.trim() == name
+        && !name.is_empty()
+        && name.chars().count() <= MAX_PROFILE_NAME
+        && name.chars().all(|c| c.is_alphanumeric() || c == ' ' || c == '-' || c == '_')
+}
+
+/// Get the path of a file belonging to a profile, relative to the data directory.
+fn profile_file(profile: &str, file: &str) -> String {
+    format!("{}/{}/{}", PROFILE_DIR, profile, file)
+}
+
+/// Get the names of every profile, in alphabetical order.
+pub fn list_profiles() -> Result<Vec<String>, StorageError> {
+    let dir: path::PathBuf = data_dir()?.join(PROFILE_DIR);
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut profiles: Vec<String> = vec![];
+    for entry in fs::read_dir(dir)? {
+        let entry: fs::DirEntry = entry?;
+        let name: String = entry.file_name().to_string_lossy().into_owned();
+        if entry.file_type()?.is_dir() && is_valid_profile_name(&name) {
+            profiles.push(name);
+        }
+    }
+    profiles.sort_by_key(|name| name.to_lowercase());
+    Ok(profiles)
+}
+
+/// Returns true if there is a profile with the given name.
+pub fn has_profile(name: &str) -> bool {
+    is_valid_profile_name(name) && data_dir().is_ok_and(|dir| dir.join(PROFILE_DIR).join(name).is_dir())
+}
+
+/// Create a new profile. The statistics kept from before there were profiles are handed to the first one.
+pub fn create_profile(name: &str) -> Result<(), StorageError> {
+    let first: bool = list_profiles()?.is_empty();
+    let dir: path::PathBuf = data_dir()?.join(PROFILE_DIR).join(name);
+    fs::create_dir_all(&dir)?;
+
+    let legacy: path::PathBuf = data_dir()?.join(STATS_FILE);
+    if first && legacy.is_file() {
+        fs::rename(legacy, dir.join(STATS_FILE))?;
+    }
+    Ok(())
+}
+
+/// Remove a profile along with everything that belongs to it, including its rating.
+pub fn delete_profile(name: &str) -> Result<(), StorageError> {
+    if has_profile(name) {
+        fs::remove_dir_all(data_dir()?.join(PROFILE_DIR).join(name))?;
+    }
+    let mut ladder: rating::Ladder = load_ladder()?;
+    ladder.remove_profile(name);
+    save_ladder(&ladder)?;
+    let mut series: rating::Series = load_series()?;
+    series.remove_profile(name);
+    save_series(&series)?;
+    if active_profile().as_deref() == Some(name) {
+        fs::remove_file(data_dir()?.join(ACTIVE_PROFILE_FILE))?;
+    }
+    Ok(())
+}
+
+/// Get the name of the profile that was last played with, if it still exists.
+pub fn active_profile() -> Option<String> {
+    let name: String = fs::read_to_string(data_dir().ok()?.join(ACTIVE_PROFILE_FILE)).ok()?;
+    Some(name.trim().to_string()).filter(|name| has_profile(name))
+}
+
+/// Remember the profile to play with, so it is picked again the next time the game starts.
+pub fn set_active_profile(name: &str) -> Result<(), StorageError> {
+    fs::write(data_dir()?.join(ACTIVE_PROFILE_FILE), name)?;
+    Ok(())
+}
+
+/// Load a profile's statistics. A profile that hasn't finished a game yet starts out with none.
+pub fn load_statistics(profile: &str) -> Result<stats::Statistics, StorageError> {
+    if !data_dir()?.join(profile_file(profile, STATS_FILE)).is_file() {
+        return Ok(stats::Statistics::default());
+    }
+    read(&profile_file(profile, STATS_FILE))
+}
+
+/// Save a profile's statistics.
+pub fn save_statistics(profile: &str, statistics: &stats::Statistics) -> Result<(), StorageError> {
+    write(&profile_file(profile, STATS_FILE), statistics)
+}
+
+/// Load the achievements a profile has unlocked.
+pub fn load_achievements(profile: &str) -> Result<achievements::Achievements, StorageError> {
+    if !data_dir()?.join(profile_file(profile, ACHIEVEMENTS_FILE)).is_file() {
+        return Ok(achievements::Achievements::default());
+    }
+    read(&profile_file(profile, ACHIEVEMENTS_FILE))
+}
+
+/// Save the achievements a profile has unlocked.
+pub fn save_achievements(profile: &str, achievements: &achievements::Achievements) -> Result<(), StorageError> {
+    write(&profile_file(profile, ACHIEVEMENTS_FILE), achievements)
+}
+
+/// Load the fleet formations a profile has saved, in the order they were saved in.
+pub fn load_formations(profile: &str) -> Result<Vec<formations::Formation>, StorageError> {
+    if !data_dir()?.join(profile_file(profile, FORMATIONS_FILE)).is_file() {
+        return Ok(vec![]);
+    }
+    read(&profile_file(profile, FORMATIONS_FILE))
+}
+
+/// Save the fleet formations of a profile.
+pub fn save_formations(profile: &str, formations: &[formations::Formation]) -> Result<(), StorageError> {
+    write(&profile_file(profile, FORMATIONS_FILE), &formations)
+}
+
+/// Load the ratings. Everyone starts out with their initial rating until they finish a rated game.
+pub fn load_ladder() -> Result<rating::Ladder, StorageError> {
+    if !data_dir()?.join(RATINGS_FILE).is_file() {
+        return Ok(rating::Ladder::default());
+    }
+    read(RATINGS_FILE)
+}
+
+/// Save the ratings.
+pub fn save_ladder(ladder: &rating::Ladder) -> Result<(), StorageError> {
+    write(RATINGS_FILE, ladder)
+}
+
+/// Load the series being played, or no series at all if none has been started yet.
+pub fn load_series() -> Result<rating::Series, StorageError> {
+    if !data_dir()?.join(SERIES_FILE).is_file() {
+        return Ok(rating::Series::default());
+    }
+    read(SERIES_FILE)
+}
+
+/// Save the series being played.
+pub fn save_series(series: &rating::Series) -> Result<(), StorageError> {
+    write(SERIES_FILE, series)
+}
+
+/// Get the name of a file in the given directory for a replay, named after when the game started.
+/// If another file already has that name, like one for an imported game that started at the same
+/// time, a number is added so neither file is overwritten.
+fn replay_file_name(dir: &path::Path, replay: &replay::Replay, extension: &str) -> String {
+    let mut name: String = format!("{}.{}", replay.started(), extension);
+    let mut copy: usize = 1;
+    while dir.join(&name).exists() {
+        copy += 1;
+        name = format!("{}-{}.{}", replay.started(), copy, extension);
+    }
+    name
+}
+
+/// Save the replay of a game along with the version of the replay format. Every replay gets a file
+/// of its own, named after when the game started.
+pub fn save_replay(replay: &replay::Replay) -> Result<(), StorageError> {
+    let dir: path::PathBuf = data_dir()?.join(REPLAY_DIR);
+    fs::create_dir_all(&dir)?;
+    let name: String = replay_file_name(&dir, replay, "bin");
+    fs::write(dir.join(name), versioned(REPLAY_MAGIC, REPLAY_VERSION, replay)?)?;
+    Ok(())
+}
+
+/// Read a saved replay. Replays written in another version of the replay format can't be read.
+fn read_replay(path: &path::Path) -> Result<replay::Replay, StorageError> {
+    let bytes: Vec<u8> = fs::read(path)?;
+    let replay: &[u8] = unversioned(&bytes, REPLAY_MAGIC, REPLAY_VERSION).ok_or(StorageError::IncompatibleReplay)?;
+    Ok(bincode::deserialize(replay)?)
+}
+
+/// Write a replay in the text notation, so it can be shared. Returns the path of the written file.
+pub fn export_notation(replay: &replay::Replay) -> Result<path::PathBuf, StorageError> {
+    let dir: path::PathBuf = data_dir()?.join(NOTATION_DIR);
+    fs::create_dir_all(&dir)?;
+    let path: path::PathBuf = dir.join(replay_file_name(&dir, replay, "txt"));
+    fs::write(&path, notation::export(replay))?;
+    Ok(path)
+}
+
+/// A file that couldn't be read, along with why.
+pub type UnreadableFile = (path::PathBuf, StorageError);
+
+/// Load every saved replay, newest first, along with the paths of the replay files that can't be
+/// read and why, so they can be reported instead of quietly going missing.
+pub fn load_replays() -> Result<(Vec<replay::Replay>, Vec<UnreadableFile>), StorageError> {
+    let dir: path::PathBuf = data_dir()?.join(REPLAY_DIR);
+    if !dir.is_dir() {
+        return Ok((vec![], vec![]));
+    }
+
+    let mut replays: Vec<replay::Replay> = vec![];
+    let mut unreadable: Vec<UnreadableFile> = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path: path::PathBuf = entry?.path();
+        // only replays are saved in the directory, so anything else was put there by someone else
+        if path.extension().is_none_or(|extension| extension != "bin") {
+            continue;
+        }
+        match read_replay(&path) {
+            Ok(replay) => replays.push(replay),
+            Err(e) => unreadable.push((path, e)),
+        }
+    }
+    replays.sort_by_key(|replay| std::cmp::Reverse(replay.started()));
+    Ok((replays, unreadable))
+}
+
+/// Remove a replay file that can't be read.
+pub fn delete_replay_file(path: &path::Path) -> Result<(), StorageError> {
+    fs::remove_file(path)?;
+    Ok(())
+}
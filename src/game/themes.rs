@@ -0,0 +1,189 @@
+/// This module contains the colour themes the boards can be drawn with. A theme decides the
+/// glyph, colour and emphasis of every kind of cell, so the states stay apart on any terminal.
+use std::{fmt, sync::atomic};
+use crossterm::style::{self, Stylize};
+use serde::{Deserialize, Serialize};
+use crate::game::components::board;
+use crate::game::{animation, settings, storage};
+
+
+/// Whether only ASCII characters are drawn, and whether that was asked for when the game was started.
+static ASCII: atomic::AtomicBool = atomic::AtomicBool::new(false);
+static FORCED_ASCII: atomic::AtomicBool = atomic::AtomicBool::new(false);
+
+/// Draw only ASCII characters for the rest of the run, whatever the settings say.
+pub fn force_ascii() {
+    FORCED_ASCII.store(true, atomic::Ordering::Relaxed);
+    ASCII.store(true, atomic::Ordering::Relaxed);
+}
+
+/// Switch to the characters the given setting asks for, unless ASCII was forced.
+pub fn use_charset(charset: settings::Charset) {
+    ASCII.store(FORCED_ASCII.load(atomic::Ordering::Relaxed) || charset.is_ascii(), atomic::Ordering::Relaxed);
+}
+
+/// Returns true if only ASCII characters are drawn.
+pub fn is_ascii() -> bool {
+    ASCII.load(atomic::Ordering::Relaxed)
+}
+
+/// An enum defining every colour theme.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    Classic,
+    HighContrast,
+    ColourBlind,
+    Light,
+    Monochrome,
+}
+
+impl fmt::Display for Theme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Theme::Classic => write!(f, "Classic"),
+            Theme::HighContrast => write!(f, "High contrast"),
+            Theme::ColourBlind => write!(f, "Colour-blind safe"),
+            Theme::Light => write!(f, "Light terminal"),
+            Theme::Monochrome => write!(f, "Monochrome")
+        }
+    }
+}
+
+impl Theme {
+    /// A static array containing every theme to iterate over.
+    pub const ALL: [Theme; 5] = [
+        Theme::Classic,
+        Theme::HighContrast,
+        Theme::ColourBlind,
+        Theme::Light,
+        Theme::Monochrome
+    ];
+
+    /// Generate a consuming iterator over the themes
+    pub fn iter() -> impl Iterator<Item = Theme> {
+        Self::ALL.into_iter()
+    }
+
+    /// Get the theme chosen in the settings file, using the classic theme if it can't be read.
+    pub fn load() -> Self {
+        storage::load_settings().unwrap_or_default().display.theme
+    }
+
+    /// Get the name the theme is written as in the settings file.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Theme::Classic => "classic",
+            Theme::HighContrast => "high-contrast",
+            Theme::ColourBlind => "colour-blind",
+            Theme::Light => "light",
+            Theme::Monochrome => "monochrome",
+        }
+    }
+
+    /// Get the glyph a cell in the given state is drawn with. Ships are always drawn with their
+    /// letter, which is already ASCII.
+    pub fn glyph(&self, state: board::CellState) -> String {
+        match (state, is_ascii()) {
+            (board::CellState::Empty, false) => "□".to_string(),
+            (board::CellState::Empty, true) => ".".to_string(),
+            (board::CellState::Guessed, false) => "▣".to_string(),
+            (board::CellState::Guessed, true) => "o".to_string(),
+            (board::CellState::OwnShip(ship_type), _) => ship_type.symbol().to_string(),
+            (board::CellState::HitShip | board::CellState::Highlighted, false) => "◼".to_string(),
+            (board::CellState::HitShip, true) => "X".to_string(),
+            (board::CellState::Highlighted, true) => "#".to_string(),
+            (board::CellState::InvalidPlacement, false) => "X".to_string(),
+            (board::CellState::InvalidPlacement, true) => "!".to_string(),
+        }
+    }
+
+    /// Get the colour a cell in the given state is drawn in, and the attribute that makes it stand
+    /// out. The colour-blind palette keeps hits and ships apart by blue and orange rather than red
+    /// and green, and the monochrome one relies on the glyphs and attributes alone.
+    fn paint(&self, state: board::CellState) -> (Option<style::Color>, Option<style::Attribute>) {
+        use board::CellState::{Empty, Guessed, Highlighted, HitShip, InvalidPlacement, OwnShip};
+        use style::Attribute::{Bold, Dim, Reverse};
+        use style::Color::{AnsiValue, Blue, Cyan, DarkBlue, DarkGreen, DarkGrey, DarkMagenta, DarkRed, Green, Magenta, Red, White, Yellow};
+        match (self, state) {
+            (Theme::Classic, Empty) => (Some(DarkGrey), None),
+            (Theme::Classic, Guessed) => (Some(White), None),
+            (Theme::Classic, OwnShip(_)) => (Some(Green), None),
+            (Theme::Classic, HitShip | InvalidPlacement) => (Some(Red), None),
+            (Theme::Classic, Highlighted) => (Some(Blue), None),
+
+            (Theme::HighContrast, Empty) => (Some(White), None),
+            (Theme::HighContrast, Guessed) => (Some(Cyan), Some(Bold)),
+            (Theme::HighContrast, OwnShip(_)) => (Some(Yellow), Some(Bold)),
+            (Theme::HighContrast, HitShip | InvalidPlacement) => (Some(Red), Some(Bold)),
+            (Theme::HighContrast, Highlighted) => (Some(Magenta), Some(Reverse)),
+
+            // these are the closest 256 colour matches to the Okabe-Ito palette
+            (Theme::ColourBlind, Empty) => (Some(DarkGrey), None),
+            (Theme::ColourBlind, Guessed) => (Some(AnsiValue(74)), None),
+            (Theme::ColourBlind, OwnShip(_)) => (Some(AnsiValue(33)), None),
+            (Theme::ColourBlind, HitShip) => (Some(AnsiValue(208)), Some(Bold)),
+            (Theme::ColourBlind, Highlighted) => (Some(AnsiValue(227)), None),
+            (Theme::ColourBlind, InvalidPlacement) => (Some(AnsiValue(208)), None),
+
+            (Theme::Light, Empty) => (Some(AnsiValue(245)), None),
+            (Theme::Light, Guessed) => (Some(style::Color::Black), None),
+            (Theme::Light, OwnShip(_)) => (Some(DarkGreen), Some(Bold)),
+            (Theme::Light, HitShip) => (Some(DarkRed), Some(Bold)),
+            (Theme::Light, Highlighted) => (Some(DarkBlue), None),
+            (Theme::Light, InvalidPlacement) => (Some(DarkMagenta), Some(Bold)),
+
+            (Theme::Monochrome, Empty) => (None, Some(Dim)),
+            (Theme::Monochrome, Guessed) => (None, None),
+            (Theme::Monochrome, OwnShip(_) | HitShip | InvalidPlacement) => (None, Some(Bold)),
+            (Theme::Monochrome, Highlighted) => (None, Some(Reverse)),
+        }
+    }
+
+    /// Get a cell in the given state as it is printed on the board.
+    pub fn cell(&self, state: board::CellState) -> style::StyledContent<String> {
+        let (colour, attribute) = self.paint(state);
+        let mut content: style::StyledContent<String> = style::style(self.glyph(state));
+        if let Some(colour) = colour {
+            content = content.with(colour);
+        }
+        if let Some(attribute) = attribute {
+            content = content.attribute(attribute);
+        }
+        content
+    }
+
+    /// Get a spark of an animation as it is printed over a cell. Sparks take the colours of the
+    /// cells they stand for, so misses look like guesses and explosions like hits.
+    pub fn spark(&self, spark: animation::Spark) -> style::StyledContent<String> {
+        let (glyph, state, attribute): (Option<&str>, board::CellState, Option<style::Attribute>) = match (spark, is_ascii()) {
+            (animation::Spark::Target, false) => (Some("◎"), board::CellState::Highlighted, Some(style::Attribute::Bold)),
+            (animation::Spark::Target, true) => (Some("+"), board::CellState::Highlighted, Some(style::Attribute::Bold)),
+            (animation::Spark::Splash, false) => (Some("≈"), board::CellState::Guessed, Some(style::Attribute::Bold)),
+            (animation::Spark::Ripple, false) | (animation::Spark::Splash, true) => (Some("~"), board::CellState::Guessed, None),
+            (animation::Spark::Ripple, true) => (Some("-"), board::CellState::Guessed, None),
+            (animation::Spark::Blast, false) => (Some("✸"), board::CellState::HitShip, Some(style::Attribute::Bold)),
+            (animation::Spark::Blast, true) => (Some("*"), board::CellState::HitShip, Some(style::Attribute::Bold)),
+            (animation::Spark::Flash | animation::Spark::Outline, _) => (None, board::CellState::HitShip, Some(style::Attribute::Reverse)),
+        };
+        // flashes keep the glyph of the cell, only drawing it reversed
+        let mut content: style::StyledContent<String> = style::style(glyph.map_or(self.glyph(state), str::to_string));
+        if let (Some(colour), _) = self.paint(state) {
+            content = content.with(colour);
+        }
+        if let Some(attribute) = attribute {
+            content = content.attribute(attribute);
+        }
+        content
+    }
+
+    /// Get the colour the row and column labels around a board are drawn in.
+    pub fn label_colour(&self) -> style::Color {
+        match self {
+            Theme::Classic | Theme::ColourBlind => style::Color::DarkGrey,
+            Theme::HighContrast => style::Color::White,
+            Theme::Light => style::Color::AnsiValue(245),
+            Theme::Monochrome => style::Color::Reset,
+        }
+    }
+}
@@ -1,66 +1,380 @@
 use battleship::{
     game::{
         self,
-        layouts::{self, TerminalLayout, menus::{self, new_game_menu::NewGameMenuOptions}}
+        components::{ai, player},
+        rating,
+        game::Controller,
+        layouts::{self, TerminalLayout, menus::{self, new_game_menu::NewGameMenuOptions, profile_menu::ProfileAction}},
+        notation,
+        rules,
+        settings,
+        storage,
+        themes
     }
 };
 
+/// Names of the players in each seat of a free-for-all game.
+const HUMAN_NAMES: [&str; layouts::game::seat_setup::MAX_SEATS] = ["Player 1", "Player 2", "Player 3", "Player 4"];
+const COMPUTER_NAMES: [&str; layouts::game::seat_setup::MAX_SEATS] = ["Computer 1", "Computer 2", "Computer 3", "Computer 4"];
+
+/// Put the settings of the active profile to use, letting the user know if they can't be used.
+fn apply_settings() {
+    themes::use_charset(storage::load_settings().unwrap_or_default().display.charset);
+    if let Err(e) = storage::load_settings() {
+        let path: std::path::PathBuf = storage::active_settings_path().unwrap_or_else(|_| storage::settings_path());
+        let mut lines: Vec<String> = vec![format!("The settings in {} can't be used:", path.display()), String::new()];
+        lines.extend(e.to_string().lines().map(|line| format!("  {}", line)));
+        lines.extend([String::new(), "The default settings are used until the file is fixed, or saved from the Settings menu.".to_string()]);
+        menus::notice::show(&lines).expect("Unexpected error encountered, exiting the game.");
+    }
+}
+
+/// Let the user pick, create and delete profiles until they pick one or go back. Returns
+/// the profile to play as, if there is one. Every profile has settings of its own, which are
+/// put to use as soon as it is picked.
+fn manage_profiles() -> Option<String> {
+    loop {
+        let profiles: Vec<String> = storage::list_profiles().expect("Failed to load profiles");
+        let active: Option<String> = storage::active_profile();
+        match menus::profile_menu::show(&profiles, active.as_deref()).expect("Unexpected error encountered, exiting the game.") {
+            ProfileAction::Select(name) => {
+                storage::set_active_profile(&name).expect("Failed to switch profiles");
+                apply_settings();
+                return Some(name);
+            },
+            ProfileAction::Create(name) => {
+                storage::create_profile(&name).expect("Failed to create the profile");
+                storage::set_active_profile(&name).expect("Failed to switch profiles");
+                apply_settings();
+                return Some(name);
+            },
+            ProfileAction::Delete(name) => storage::delete_profile(&name).expect("Failed to delete the profile"),
+            ProfileAction::Back => return active,
+        }
+    }
+}
+
+/// Let the second player of a hot-seat game pick or create a profile other than the given one,
+/// so the game can be rated for both of them. Returns None if they go back to play without one.
+fn pick_opponent(profile: &str) -> Option<String> {
+    loop {
+        let profiles: Vec<String> = storage::list_profiles().expect("Failed to load profiles")
+            .into_iter()
+            .filter(|name| name != profile)
+            .collect();
+        match menus::profile_menu::show(&profiles, None).expect("Unexpected error encountered, exiting the game.") {
+            ProfileAction::Select(name) => return Some(name),
+            ProfileAction::Create(name) => {
+                storage::create_profile(&name).expect("Failed to create the profile");
+                return Some(name);
+            },
+            ProfileAction::Delete(name) => storage::delete_profile(&name).expect("Failed to delete the profile"),
+            ProfileAction::Back => return None,
+        }
+    }
+}
+
+/// Create the players for a new game and let every human place their ships. The humans play as
+/// the given profiles in the order they are seated, and any humans left over play under the name
+/// of their seat. Returns None if one of them quits during setup.
+fn set_up_game(controllers: Vec<Controller>, game_rules: rules::GameRules, profiles: &[String]) -> Option<game::game::Game> {
+    // players are numbered by their seat, unless it's just one human against the computer
+    let humans: usize = controllers.iter().filter(|controller| **controller == Controller::Human).count();
+    let numbered: bool = controllers.len() > 2 || humans > 1;
+    let mut seats: Vec<(player::Player, Controller)> = vec![];
+    for (seat, controller) in controllers.into_iter().enumerate() {
+        let profile: Option<&str> = match controller {
+            Controller::Human => profiles.get(seats.iter().filter(|(_, other)| *other == Controller::Human).count()).map(String::as_str),
+            Controller::Computer(_) => None,
+        };
+        let mut player = match (controller, profile, numbered) {
+            (Controller::Human, Some(profile), _) => player::Player::for_profile(profile),
+            (Controller::Human, None, true) => player::Player::new(HUMAN_NAMES[seat]),
+            (Controller::Human, None, false) => player::Player::new("Player"),
+            (Controller::Computer(_), _, true) => player::Player::new(COMPUTER_NAMES[seat]),
+            (Controller::Computer(_), _, false) => player::Player::new("Computer"),
+        };
+
+        match controller {
+            // let the player set up their board
+            Controller::Human => {
+                // hand the computer over before anyone but the first human places their ships
+                if seats.iter().any(|(_, other)| *other == Controller::Human)
+                    && !layouts::game::pass_screen::show(player.get_name()).unwrap_or(false) {
+                    return None;
+                }
+                if let layouts::game::board_setup::ShipSetupOption::Quit = layouts::game::board_setup::show(&mut player, &game_rules.fleet(), profile).expect("Failed to setup player ships") {
+                    return None;
+                }
+            },
+            // setup the computer's board automatically
+            Controller::Computer(_) => player.auto_place_ships(&game_rules.fleet(), 100, 10).expect("Failed to auto-place computer ships"),
+        }
+        seats.push((player, controller));
+    }
+
+    Some(game::game::Game::new(seats, game_rules))
+}
+
+/// Read games written in the text notation and save them as replays, so they can be watched
+/// from the replays menu. Returns false if any of the files couldn't be imported.
+fn import_games(files: &[String]) -> bool {
+    let mut imported: bool = true;
+    for file in files {
+        let result = std::fs::read_to_string(file)
+            .map_err(|err| err.to_string())
+            .and_then(|text| notation::import(&text).map_err(|err| err.to_string()))
+            .and_then(|replay| storage::save_replay(&replay).map(|_| replay).map_err(|err| err.to_string()));
+        match result {
+            Ok(replay) => println!("{}: imported {}", file, replay.summary()),
+            Err(err) => {
+                eprintln!("{}: {}", file, err);
+                imported = false;
+            }
+        }
+    }
+    imported
+}
+
 fn main() {
+    // games shared in the text notation are imported with `battleship import <file>...`, and
+    // `battleship --ascii` draws the game without unicode glyphs whatever the settings say
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some("import") = args.first().map(String::as_str) {
+        if args.len() < 2 {
+            eprintln!("Usage: battleship import <file>...");
+            std::process::exit(2);
+        }
+        std::process::exit(if import_games(&args[1..]) { 0 } else { 1 });
+    }
+    if args.iter().any(|arg| arg == "--ascii") {
+        themes::force_ascii();
+    }
+
+    // let the user know if their settings can't be used, before anything else
+    apply_settings();
+
     // the very first thing we want to do is show the user the
     // main menu
-    let game_type_option: Option<NewGameMenuOptions> = 'showingMenus: loop {
+    let mut continued: bool = false;
+    let game_option: Option<game::game::Game> = 'showingMenus: loop {
+        // everything the player does belongs to a profile, so make sure one is picked first
+        let Some(profile) = storage::active_profile().or_else(manage_profiles) else {
+            println!("Thanks for playing!");
+            break 'showingMenus None
+        };
+
         let option = menus::main_menu::MainMenu::show();
 
         // check if the user asked to quit the game and early return
         if let Ok(menus::main_menu::MainMenuOptions::Quit) = option {
             println!("Thanks for playing!");
             break 'showingMenus None
-        } else if let Err(_) = option {
+        } else if option.is_err() {
             panic!("Unexpected error encountered, exiting the game.")
         }
 
+        // show the player's statistics, saving them again if the player reset them
+        if let Ok(menus::main_menu::MainMenuOptions::Statistics) = option {
+            let mut statistics = storage::load_statistics(&profile).expect("Failed to load statistics");
+            let rating: i32 = storage::load_ladder().expect("Failed to load ratings").profile_rating(&profile);
+            let unlocked = storage::load_achievements(&profile).expect("Failed to load achievements");
+            if menus::statistics_screen::show(&profile, rating, &mut statistics, &unlocked).expect("Unexpected error encountered, exiting the game.") {
+                storage::save_statistics(&profile, &statistics).expect("Failed to save statistics");
+            }
+        }
+
+        // change the settings, keeping the defaults for any that couldn't be loaded
+        if let Ok(menus::main_menu::MainMenuOptions::Settings) = option {
+            let mut current: settings::Settings = storage::load_settings().unwrap_or_default();
+            if menus::settings_menu::show(&mut current).expect("Unexpected error encountered, exiting the game.") {
+                storage::save_settings(&current).expect("Failed to save settings");
+            }
+            themes::use_charset(current.display.charset);
+        }
+
+        // switch to another profile, or create or delete one
+        if let Ok(menus::main_menu::MainMenuOptions::Profiles) = option {
+            manage_profiles();
+        }
+
+        // let the player pick a replay to watch, and return to the list once they are done with it
+        if let Ok(menus::main_menu::MainMenuOptions::Replays) = option {
+            let (replays, unreadable) = storage::load_replays().expect("Failed to load replays");
+            // replays that can't be read would be reported again on every visit, so offer to get rid of them
+            if !unreadable.is_empty() {
+                let mut lines: Vec<String> = vec!["These replays can't be watched:".to_string()];
+                lines.extend(unreadable.iter().map(|(path, e)| format!("  {}: {}", path.display(), e)));
+                if menus::notice::confirm(&lines, "Delete them?").expect("Unexpected error encountered, exiting the game.") {
+                    for (path, _) in &unreadable {
+                        if let Err(e) = storage::delete_replay_file(path) {
+                            menus::notice::show(&[format!("{} could not be deleted: {}", path.display(), e)]).expect("Unexpected error encountered, exiting the game.");
+                        }
+                    }
+                }
+            }
+            while let Some(index) = menus::replay_menu::show(&replays).expect("Unexpected error encountered, exiting the game.") {
+                layouts::game::replay_viewer::show(&replays[index]).expect("Unexpected error encountered, exiting the game.");
+            }
+        }
+
+        // pick up the saved game where it was left
+        if let Ok(menus::main_menu::MainMenuOptions::Continue) = option {
+            match storage::load_game() {
+                Ok(game_instance) => {
+                    continued = true;
+                    break 'showingMenus Some(game_instance)
+                },
+                // a save that can't be read would fail again on every try, so offer to get rid of it
+                Err(e) => {
+                    let lines: Vec<String> = vec![
+                        "The saved game can't be continued.".to_string(),
+                        format!("  {}", e),
+                    ];
+                    if menus::notice::confirm(&lines, "Delete the saved game?").expect("Unexpected error encountered, exiting the game.")
+                        && let Err(e) = storage::delete_saved_game() {
+                        menus::notice::show(&[format!("The saved game could not be deleted: {}", e)]).expect("Unexpected error encountered, exiting the game.");
+                    }
+                }
+            }
+        }
+
         // if the new game menu selected, send them to the new game screen
         if let Ok(menus::main_menu::MainMenuOptions::NewGame) = option {
-            match menus::new_game_menu::NewGameMenu::show() {
-                Ok(menus::new_game_menu::NewGameMenuOptions::PlayComputer) => break 'showingMenus Some(menus::new_game_menu::NewGameMenuOptions::PlayComputer),
-                Ok(menus::new_game_menu::NewGameMenuOptions::JoinGame) => print!("Joining a game"),
-                Ok(menus::new_game_menu::NewGameMenuOptions::HostGame) => print!("Hosting a game"),
-                Ok(menus::new_game_menu::NewGameMenuOptions::Back) => { /* do nothing; just go back to main menu loop */ },
+            // the profile the second human of a hot-seat game plays as, if they picked one
+            let mut opponent: Option<String> = None;
+            let new_game: Option<(Vec<Controller>, rules::GameRules)> = match menus::new_game_menu::NewGameMenu::show() {
+                Ok(NewGameMenuOptions::PlayComputer) => {
+                    // pick the rules for the game. backing out of either menu returns to the main menu
+                    let Some(mode) = menus::game_mode_menu::GameModeMenu::show().expect("Unexpected error encountered, exiting the game.") else { continue };
+                    let Some(difficulty) = menus::difficulty_menu::DifficultyMenu::show().expect("Unexpected error encountered, exiting the game.") else { continue };
+                    let seats: Vec<Controller> = vec![Controller::Human, Controller::Computer(difficulty)];
+                    Some((seats, rules::GameRules::new(mode, difficulty)))
+                },
+                Ok(NewGameMenuOptions::PlayPlayer) => {
+                    // two humans take turns on the same computer, so only the rules and who the second one is need picking
+                    let Some(mode) = menus::game_mode_menu::GameModeMenu::show().expect("Unexpected error encountered, exiting the game.") else { continue };
+                    opponent = pick_opponent(&profile);
+                    let seats: Vec<Controller> = vec![Controller::Human, Controller::Human];
+                    Some((seats, rules::GameRules::new(mode, ai::Difficulty::Normal)))
+                },
+                Ok(NewGameMenuOptions::FreeForAll) => {
+                    // pick the rules and who sits where. backing out of either screen returns to the main menu
+                    let Some(mode) = menus::game_mode_menu::GameModeMenu::show().expect("Unexpected error encountered, exiting the game.") else { continue };
+                    let Some(seats) = layouts::game::seat_setup::show(false).expect("Unexpected error encountered, exiting the game.") else { continue };
+                    // every computer brings its own difficulty, so the rules just remember the first one
+                    let difficulty: ai::Difficulty = seats.iter()
+                        .find_map(|seat| match seat { Controller::Computer(difficulty) => Some(*difficulty), _ => None })
+                        .unwrap_or(ai::Difficulty::Normal);
+                    Some((seats, rules::GameRules::new(mode, difficulty)))
+                },
+                Ok(NewGameMenuOptions::TeamPlay) => {
+                    // same as a free-for-all, except the seats are split into two teams
+                    let Some(mode) = menus::game_mode_menu::GameModeMenu::show().expect("Unexpected error encountered, exiting the game.") else { continue };
+                    let Some(seats) = layouts::game::seat_setup::show(true).expect("Unexpected error encountered, exiting the game.") else { continue };
+                    let difficulty: ai::Difficulty = seats.iter()
+                        .find_map(|seat| match seat { Controller::Computer(difficulty) => Some(*difficulty), _ => None })
+                        .unwrap_or(ai::Difficulty::Normal);
+                    let mut game_rules: rules::GameRules = rules::GameRules::new(mode, difficulty);
+                    game_rules.teams = true;
+                    Some((seats, game_rules))
+                },
+                Ok(NewGameMenuOptions::JoinGame) => {
+                    let network = storage::load_settings().unwrap_or_default().network;
+                    print!("Joining a game at {}:{}", network.host, network.port);
+                    None
+                },
+                Ok(NewGameMenuOptions::HostGame) => {
+                    let network = storage::load_settings().unwrap_or_default().network;
+                    print!("Hosting a game on port {}", network.port);
+                    None
+                },
+                Ok(NewGameMenuOptions::Back) => None, // do nothing; just go back to main menu loop
                 Err(_) => panic!("Unexpected error encountered, exiting the game.")
+            };
+
+            // let everyone set up their boards before the game starts
+            if let Some((seats, game_rules)) = new_game {
+                let profiles: Vec<String> = std::iter::once(profile.clone()).chain(opponent).collect();
+                match set_up_game(seats, game_rules, &profiles) {
+                    Some(game_instance) => break 'showingMenus Some(game_instance),
+                    None => {
+                        println!("Thanks for playing!");
+                        return;
+                    }
+                }
             }
-        }   
+        }
     };
 
     // at this stage, we can begin the game!
-    match game_type_option.unwrap() {
-        menus::new_game_menu::NewGameMenuOptions::PlayComputer => {
-            // create a new game against the computer
-            let mut player = game::components::player::Player::new("Player");
-            let mut computer_player = game::components::player::Player::new("Computer");
+    if let Some(mut game_instance) = game_option {
+        // start the game loop
+        let game_end = game_instance.start_loop();
 
-            // let the player set up their board
-            layouts::game::board_setup::show(&mut player).expect("Failed to setup player ships");
+        // once the saved game has been won, it can't be continued anymore
+        if continued && let Ok(game::game::GameEndReason::PlayerWon(_) | game::game::GameEndReason::TeamWon(_)) = game_end {
+            storage::delete_saved_game().expect("Failed to remove the saved game");
+        }
 
-            // setup the computer's board automatically
-            computer_player.auto_place_ships(100, 10).expect("Failed to auto-place computer ships");
-
-            // start the game loop
-            
-            let mut game_instance = game::game::Game::new(player, computer_player);
-            
-            match game_instance.start_loop() {
-                Ok(winner) => {
-                    layouts::game::win_screen::show(game_instance.get_player_a(), game_instance.get_player_b(),
-                    match winner {
-                        game::game::GameEndReason::PlayerAWon => "Player",
-                        game::game::GameEndReason::PlayerBWon => "Computer"
-                    }
-                ).expect("Failed to show win screen");
+        // keep a recording of every finished game
+        if let Ok(game::game::GameEndReason::PlayerWon(_) | game::game::GameEndReason::TeamWon(_)) = game_end {
+            storage::save_replay(game_instance.get_replay()).expect("Failed to save the replay");
+        }
+
+        // rate the game for every human that played as a profile that still exists. results are credited
+        // to the profile each seat was set up with, never to a profile that happens to share a seat's name
+        let mut rating_changes: Vec<rating::Change> = vec![];
+        let mut standing: Option<rating::Standing> = None;
+        if let Ok(end) = &game_end {
+            let players: &[player::Player] = game_instance.get_players();
+            let profiles: Vec<&str> = game_instance.human_seats().into_iter()
+                .filter_map(|seat| players[seat].get_profile())
+                .filter(|profile| storage::has_profile(profile))
+                .collect();
+            if !profiles.is_empty() {
+                let mut ladder = storage::load_ladder().expect("Failed to load ratings");
+                let mut series = storage::load_series().expect("Failed to load series");
+                (rating_changes, standing) = ladder.record(&game_instance, end, &profiles, &mut series);
+                storage::save_ladder(&ladder).expect("Failed to save ratings");
+                storage::save_series(&series).expect("Failed to save series");
+            }
+        }
+
+        // add the result to the statistics and achievements of the profile the human played as, if it still exists
+        if let Ok(end) = &game_end
+            && let [seat] = game_instance.human_seats()[..]
+            && let Some(profile) = game_instance.get_players()[seat].get_profile()
+            && storage::has_profile(profile) {
+            let mut statistics = storage::load_statistics(profile).expect("Failed to load statistics");
+            statistics.record(&game_instance, end);
+            storage::save_statistics(profile, &statistics).expect("Failed to save statistics");
+
+            let mut unlocked = storage::load_achievements(profile).expect("Failed to load achievements");
+            for achievement in game_instance.get_earned() {
+                unlocked.unlock(*achievement);
+            }
+            storage::save_achievements(profile, &unlocked).expect("Failed to save achievements");
+        }
+
+        match game_end {
+            Ok(game::game::GameEndReason::PlayerWon(winner)) => {
+                let players: &[player::Player] = game_instance.get_players();
+                layouts::game::win_screen::show(players, players[winner].get_name(), &rating_changes, standing.as_ref(), game_instance.get_earned()).expect("Failed to show win screen");
                 println!("Thanks for playing!");
             },
-                Err(e) => println!("Game ended with error: {}", e)
-            }
-        },
-        _ => {}  // for now, we only support  playing the computer
+            Ok(game::game::GameEndReason::TeamWon(team)) => {
+                let players: &[player::Player] = game_instance.get_players();
+                let members: Vec<&str> = (0..players.len())
+                    .filter(|seat| game_instance.get_rules().team_of(*seat) == team)
+                    .map(|seat| players[seat].get_name())
+                    .collect();
+                let winner: String = format!("Team {} ({})", team + 1, members.join(" & "));
+                layouts::game::win_screen::show(players, &winner, &rating_changes, standing.as_ref(), game_instance.get_earned()).expect("Failed to show win screen");
+                println!("Thanks for playing!");
+            },
+            Ok(game::game::GameEndReason::Saved) => println!("Game saved. Pick Continue from the main menu to carry on playing!"),
+            Err(e) => println!("Game ended with error: {}", e)
+        }
     }
 }